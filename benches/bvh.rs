@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use raytracing::hittable::{BvhNode, Triangle, TriangleMesh};
+use raytracing::material::Lambertian;
+use raytracing::{Color, Hittable, HittablePtr, MaterialPtr, Point3, Random, Ray, Vec3};
+
+const TEAPOT: &str = "res/teapot.obj";
+
+fn material() -> MaterialPtr {
+    Arc::new(Lambertian::with_color(Color::new(0.73, 0.73, 0.73)))
+}
+
+/// The teapot as separate `Triangle` objects.
+fn teapot() -> Vec<HittablePtr> {
+    let mat = material();
+    let ob = obj::Obj::load(TEAPOT).unwrap().data;
+    ob.objects[0].groups[0]
+        .polys
+        .iter()
+        .map(|obj::SimplePolygon(poly)| {
+            let p = |i: usize| Vec3::from(ob.position[poly[i].0]);
+            Arc::new(Triangle::new(p(0), p(1), p(2), mat.clone())) as HittablePtr
+        })
+        .collect()
+}
+
+/// Rays from a sphere around the teapot aimed at points near its center.
+fn rays(rng: &mut Random, n: usize) -> Vec<Ray> {
+    (0..n)
+        .map(|_| {
+            let orig = 10.0 * Vec3::random_unit_vector(rng);
+            let target = Point3::new(0.0, 1.5, 0.0) + Vec3::random(rng, -2.0, 2.0);
+            let dir = target - &orig;
+            Ray::new(orig, dir, 0.0)
+        })
+        .collect()
+}
+
+fn builders(c: &mut Criterion) {
+    let objects = teapot();
+    let sah = BvhNode::new(&objects, 0.0, 1.0).unwrap();
+    let median = BvhNode::random_split(&objects, 0.0, 1.0, &mut Random::new(0)).unwrap();
+    println!(
+        "SAH cost over {} triangles: sah {:.2}, random split {:.2}",
+        objects.len(),
+        sah.sah_cost(),
+        median.sah_cost()
+    );
+
+    let mut group = c.benchmark_group("bvh build");
+    group.bench_function("sah", |b| {
+        b.iter(|| BvhNode::new(black_box(&objects), 0.0, 1.0))
+    });
+    group.bench_function("random split", |b| {
+        let mut rng = Random::new(0);
+        b.iter(|| BvhNode::random_split(black_box(&objects), 0.0, 1.0, &mut rng))
+    });
+    group.finish();
+
+    let rays = rays(&mut Random::new(1), 1000);
+    let mut group = c.benchmark_group("bvh hit");
+    for (name, bvh) in [("sah", &sah), ("random split", &median)] {
+        group.bench_with_input(BenchmarkId::from_parameter(name), bvh, |b, bvh| {
+            let mut rng = Random::new(2);
+            b.iter(|| {
+                rays.iter()
+                    .filter(|r| bvh.hit(r, 0.001, f64::INFINITY, &mut rng).is_some())
+                    .count()
+            })
+        });
+    }
+    group.finish();
+
+    let mesh = TriangleMesh::load(TEAPOT, material()).unwrap();
+    let mut group = c.benchmark_group("mesh hit");
+    for (name, hittable) in [
+        ("triangle mesh", &mesh as &dyn Hittable),
+        ("triangle bvh", &sah),
+    ] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            hittable,
+            |b, hittable| {
+                let mut rng = Random::new(2);
+                b.iter(|| {
+                    rays.iter()
+                        .filter(|r| hittable.hit(r, 0.001, f64::INFINITY, &mut rng).is_some())
+                        .count()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, builders);
+criterion_main!(benches);
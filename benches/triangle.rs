@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use raytracing::algebra::solve_equation;
+use raytracing::hittable::Triangle;
+use raytracing::material::Lambertian;
+use raytracing::{Color, HitRecord, Hittable, MaterialPtr, Point3, Random, Ray, Vec3};
+
+/// The previous intersection: Gaussian elimination on a freshly
+/// allocated 3x3 system.
+fn gaussian_hit(
+    p: &[Point3; 3],
+    material: &MaterialPtr,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord> {
+    let a = &p[1] - &p[0];
+    let b = &p[2] - &p[0];
+    let coef = Vec3::to_matrix(&r.dir, &-&a, &-&b);
+    let tuv = solve_equation(coef, (&p[0] - &r.orig).into());
+    let (t, u, v) = (tuv[0], tuv[1], tuv[2]);
+    if t < t_min || t > t_max || u < 0.0 || v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let normal = a.cross(&b).unit_vector();
+    Some(HitRecord::new(
+        r.at(t),
+        t,
+        u,
+        v,
+        r,
+        normal,
+        material.clone(),
+    ))
+}
+
+fn intersection(c: &mut Criterion) {
+    let material: MaterialPtr = Arc::new(Lambertian::with_color(Color::new(0.5, 0.5, 0.5)));
+    let p = [
+        Point3::new(-1.0, 0.0, 0.0),
+        Point3::new(1.0, 0.0, 0.0),
+        Point3::new(0.0, 1.5, 0.0),
+    ];
+    let triangle = Triangle::new(p[0].clone(), p[1].clone(), p[2].clone(), material.clone());
+
+    // About half of these rays hit.
+    let mut rng = Random::new(0);
+    let rays: Vec<Ray> = (0..1000)
+        .map(|_| {
+            let orig = Point3::new(0.0, 0.5, 5.0);
+            let target = Point3::new(rng.range_f64(-1.5, 1.5), rng.range_f64(-0.5, 2.0), 0.0);
+            let dir = target - &orig;
+            Ray::new(orig, dir, 0.0)
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("triangle hit");
+    group.bench_function("gaussian elimination", |b| {
+        b.iter(|| {
+            rays.iter()
+                .filter(|r| gaussian_hit(&p, &material, r, 0.001, f64::INFINITY).is_some())
+                .count()
+        })
+    });
+    group.bench_function("watertight", |b| {
+        b.iter(|| {
+            rays.iter()
+                .filter(|r| triangle.hit(r, 0.001, f64::INFINITY, &mut rng).is_some())
+                .count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, intersection);
+criterion_main!(benches);
@@ -1,5 +1,277 @@
+use std::ops;
+
 use ordered_float::OrderedFloat;
 
+use crate::{Point3, Vec3};
+
+/// A 4x4 matrix acting on column vectors, `m.0[row][col]`. Points are
+/// `(x, y, z, 1)` and vectors `(x, y, z, 0)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mat4(pub [[f64; 4]; 4]);
+
+impl Mat4 {
+    pub fn identity() -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        Self(m)
+    }
+
+    pub fn translation(offset: &Vec3) -> Self {
+        let mut m = Self::identity();
+        m.0[0][3] = offset.x;
+        m.0[1][3] = offset.y;
+        m.0[2][3] = offset.z;
+        m
+    }
+
+    pub fn scaling(factor: &Vec3) -> Self {
+        let mut m = Self::identity();
+        m.0[0][0] = factor.x;
+        m.0[1][1] = factor.y;
+        m.0[2][2] = factor.z;
+        m
+    }
+
+    /// Counter-clockwise rotation by `degrees` looking down `axis`.
+    pub fn rotation(axis: &Vec3, degrees: f64) -> Self {
+        let a = axis.unit_vector();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let c = 1.0 - cos;
+        Self([
+            [
+                cos + a.x * a.x * c,
+                a.x * a.y * c - a.z * sin,
+                a.x * a.z * c + a.y * sin,
+                0.0,
+            ],
+            [
+                a.y * a.x * c + a.z * sin,
+                cos + a.y * a.y * c,
+                a.y * a.z * c - a.x * sin,
+                0.0,
+            ],
+            [
+                a.z * a.x * c - a.y * sin,
+                a.z * a.y * c + a.x * sin,
+                cos + a.z * a.z * c,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Moves the origin to `from` and turns the -z axis towards `at` and
+    /// the y axis towards `up`, the way `Camera` looks.
+    pub fn look_at(from: &Point3, at: &Point3, up: &Vec3) -> Self {
+        let w = (from - at).unit_vector();
+        let u = up.cross(&w).unit_vector();
+        let v = w.cross(&u);
+        Self([
+            [u.x, v.x, w.x, from.x],
+            [u.y, v.y, w.y, from.y],
+            [u.z, v.z, w.z, from.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// From column-major storage as used by glTF.
+    pub fn from_cols(cols: [[f64; 4]; 4]) -> Self {
+        Self(cols).transpose()
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = self.0[j][i];
+            }
+        }
+        Self(m)
+    }
+
+    /// Gauss-Jordan elimination with partial pivoting; `None` if singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.0;
+        let mut inv = Self::identity().0;
+        for col in 0..4 {
+            let pivot = (col..4).max_by_key(|&r| OrderedFloat(a[r][col].abs()))?;
+            if a[pivot][col] == 0.0 {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+            for r in 0..4 {
+                if r != col {
+                    let f = a[r][col];
+                    for j in 0..4 {
+                        a[r][j] -= f * a[col][j];
+                        inv[r][j] -= f * inv[col][j];
+                    }
+                }
+            }
+        }
+        Some(Self(inv))
+    }
+
+    /// Determinant of the upper-left 3x3 block; negative for mirroring
+    /// transforms.
+    pub fn det3(&self) -> f64 {
+        let m = &self.0;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    pub fn transform_point(&self, p: &Point3) -> Point3 {
+        let m = &self.0;
+        let row = |r: &[f64; 4]| r[0] * p.x + r[1] * p.y + r[2] * p.z + r[3];
+        let w = row(&m[3]);
+        Point3::new(row(&m[0]) / w, row(&m[1]) / w, row(&m[2]) / w)
+    }
+
+    pub fn transform_vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.0;
+        let row = |r: &[f64; 4]| r[0] * v.x + r[1] * v.y + r[2] * v.z;
+        Vec3::new(row(&m[0]), row(&m[1]), row(&m[2]))
+    }
+
+    /// Multiplies by the transpose of the upper-left 3x3 block. Applied to
+    /// the inverse of a transform, this maps normals.
+    pub fn transform_vector_transposed(&self, v: &Vec3) -> Vec3 {
+        let m = &self.0;
+        let col = |c: usize| m[0][c] * v.x + m[1][c] * v.y + m[2][c] * v.z;
+        Vec3::new(col(0), col(1), col(2))
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl_op_ex!(*|a: &Mat4, b: &Mat4| -> Mat4 {
+    let mut m = [[0.0; 4]; 4];
+    for (i, row) in m.iter_mut().enumerate() {
+        for (j, x) in row.iter_mut().enumerate() {
+            *x = (0..4).map(|k| a.0[i][k] * b.0[k][j]).sum();
+        }
+    }
+    Mat4(m)
+});
+
+/// Unit quaternion `w + xi + yj + zk` representing a rotation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quat {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quat {
+    pub fn identity() -> Self {
+        Self {
+            w: 1.0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+
+    /// Counter-clockwise rotation by `degrees` looking down `axis`, as
+    /// `Mat4::rotation`.
+    pub fn from_axis_angle(axis: &Vec3, degrees: f64) -> Self {
+        let a = axis.unit_vector();
+        let (sin, cos) = (degrees.to_radians() / 2.0).sin_cos();
+        Self {
+            w: cos,
+            x: a.x * sin,
+            y: a.y * sin,
+            z: a.z * sin,
+        }
+    }
+
+    fn dot(&self, other: &Self) -> f64 {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// Angle in radians of the rotation taking `self` to `other`.
+    pub fn angle_to(&self, other: &Self) -> f64 {
+        2.0 * self.dot(other).abs().min(1.0).acos()
+    }
+
+    /// Spherical linear interpolation along the shorter arc, turning at a
+    /// constant rate.
+    pub fn slerp(&self, other: &Self, t: f64) -> Self {
+        let mut d = self.dot(other);
+        let sign = if d < 0.0 { -1.0 } else { 1.0 };
+        d *= sign;
+        let (s0, s1) = if d > 0.9995 {
+            // Nearly parallel; fall back to a normalized lerp.
+            (1.0 - t, t)
+        } else {
+            let theta = d.acos();
+            (
+                ((1.0 - t) * theta).sin() / theta.sin(),
+                (t * theta).sin() / theta.sin(),
+            )
+        };
+        let s1 = s1 * sign;
+        let q = Self {
+            w: s0 * self.w + s1 * other.w,
+            x: s0 * self.x + s1 * other.x,
+            y: s0 * self.y + s1 * other.y,
+            z: s0 * self.z + s1 * other.z,
+        };
+        let norm = q.dot(&q).sqrt();
+        Self {
+            w: q.w / norm,
+            x: q.x / norm,
+            y: q.y / norm,
+            z: q.z / norm,
+        }
+    }
+
+    pub fn to_mat4(&self) -> Mat4 {
+        let Self { w, x, y, z } = *self;
+        Mat4([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
 pub fn solve_equation(mut coef: Vec<Vec<f64>>, mut rhs: Vec<f64>) -> Vec<f64> {
     let n = coef.len();
     for step in 0..n {
@@ -52,4 +324,41 @@ mod tests {
             assert!((left - rhs[r]).abs() < 1e-4);
         }
     }
+
+    #[test]
+    fn mat4_inverse() {
+        let m = Mat4([
+            [0.0, -2.0, 0.0, 1.0],
+            [1.0, 0.0, 0.0, 2.0],
+            [0.0, 0.0, 3.0, -1.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let p = Point3::new(0.5, -1.0, 2.0);
+        let q = m.transform_point(&p);
+        assert_eq!(q, Point3::new(3.0, 2.5, 5.0));
+        let inv = m.inverse().unwrap();
+        assert!((inv.transform_point(&q) - p).length() < 1e-12);
+        let product = m * inv;
+        for (r, e) in product.0.iter().zip(&Mat4::identity().0) {
+            for (x, y) in r.iter().zip(e) {
+                assert!((x - y).abs() < 1e-12);
+            }
+        }
+        assert!(Mat4([[0.0; 4]; 4]).inverse().is_none());
+    }
+
+    #[test]
+    fn quat_slerp() {
+        let axis = Vec3::new(1.0, 2.0, -0.5);
+        let a = Quat::from_axis_angle(&axis, 10.0);
+        let b = Quat::from_axis_angle(&axis, 130.0);
+        let mid = a.slerp(&b, 0.25).to_mat4();
+        let expected = Mat4::rotation(&axis, 40.0);
+        for (r, e) in mid.0.iter().zip(&expected.0) {
+            for (x, y) in r.iter().zip(e) {
+                assert!((x - y).abs() < 1e-12);
+            }
+        }
+        assert!((a.angle_to(&b) - 120f64.to_radians()).abs() < 1e-12);
+    }
 }
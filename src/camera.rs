@@ -51,22 +51,15 @@ impl Camera {
         }
     }
 
-    pub fn with_scene(
-        scene: &Scene,
-        vup: Vec3,
-        aspect_ratio: f64,
-        focus_dist: f64,
-        time0: f64,
-        time1: f64,
-    ) -> Self {
+    pub fn with_scene(scene: &Scene, aspect_ratio: f64, time0: f64, time1: f64) -> Self {
         Self::new(
             scene.lookfrom.clone(),
             scene.lookat.clone(),
-            vup,
+            scene.vup.clone(),
             scene.vfov,
             aspect_ratio,
             scene.aperture,
-            focus_dist,
+            scene.focus_dist,
             time0,
             time1,
         )
@@ -1,12 +1,16 @@
 pub mod aabb;
+pub mod animated_transform;
 pub mod box_obj;
 pub mod bvh;
 pub mod constant_medium;
 pub mod hittable_list;
+pub mod instance;
+mod ply;
 pub mod rect;
 pub mod sphere;
 pub mod transform;
 pub mod triangle;
+pub mod triangle_mesh;
 
 use std::sync::Arc;
 
@@ -15,14 +19,20 @@ use crate::Random;
 use super::{MaterialPtr, Point3, Ray, Vec3};
 
 pub use aabb::Aabb;
+pub use animated_transform::{AnimatedTransform, Keyframe};
 pub use box_obj::BoxObj;
 pub use bvh::BvhNode;
 pub use constant_medium::ConstantMedium;
 pub use hittable_list::HittableList;
+pub use instance::{Instance, Instances};
 pub use rect::{XYRect, XZRect, YZRect};
 pub use sphere::{MovingSphere, Sphere};
-pub use transform::{rotate_y, translate, RotateY, Translate};
+pub use transform::{
+    look_at, rotate_axis, rotate_x, rotate_y, rotate_z, scale, transform, translate, RotateY,
+    Transform, Translate,
+};
 pub use triangle::Triangle;
+pub use triangle_mesh::{load_obj, MeshData, TriangleMesh};
 
 #[derive(Clone)]
 pub struct HitRecord {
@@ -66,6 +76,14 @@ impl HitRecord {
 pub trait Hittable {
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rng: &mut Random) -> Option<HitRecord>;
     fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb>;
+    /// Solid angle density of `random` picking direction `v` from `o`.
+    fn pdf_value(&self, _o: &Point3, _v: &Vec3, _rng: &mut Random) -> f64 {
+        0.0
+    }
+    /// Direction from `o` towards a random point on the surface.
+    fn random(&self, _o: &Point3, _rng: &mut Random) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
 }
 
 pub type HittablePtr = Arc<dyn Hittable + Send + Sync>;
@@ -39,6 +39,13 @@ impl Aabb {
         );
         Self::new(minimum, maximum)
     }
+    pub fn surface_area(&self) -> f64 {
+        let d = &self.maximum - &self.minimum;
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+    pub fn centroid(&self) -> Point3 {
+        (&self.minimum + &self.maximum) / 2.0
+    }
     pub fn merge(self, other: &Option<Self>) -> Option<Self> {
         match other {
             Some(other) => Some(self.surrounding_box(other)),
@@ -0,0 +1,200 @@
+use crate::algebra::{Mat4, Quat};
+use crate::{Random, Ray, Vec3};
+
+use super::transform::{hit_transformed, transformed_box};
+use super::{Aabb, HitRecord, Hittable, HittablePtr};
+
+/// Steps per keyframe interval when bounding the motion.
+const BOUND_STEPS: usize = 32;
+
+/// Scale, then rotation, then translation at `time`.
+#[derive(Debug, Clone)]
+pub struct Keyframe {
+    pub time: f64,
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Keyframe {
+    pub fn new(time: f64, translation: Vec3, rotation: Quat, scale: Vec3) -> Self {
+        Self {
+            time,
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::translation(&self.translation) * self.rotation.to_mat4() * Mat4::scaling(&self.scale)
+    }
+
+    fn inverse(&self) -> Mat4 {
+        let s = &self.scale;
+        Mat4::scaling(&Vec3::new(1.0 / s.x, 1.0 / s.y, 1.0 / s.z))
+            * self.rotation.to_mat4().transpose()
+            * Mat4::translation(&-&self.translation)
+    }
+
+    fn lerp(&self, other: &Self, time: f64) -> Self {
+        let t = (time - self.time) / (other.time - self.time);
+        Self {
+            time,
+            translation: (1.0 - t) * &self.translation + t * &other.translation,
+            rotation: self.rotation.slerp(&other.rotation, t),
+            scale: (1.0 - t) * &self.scale + t * &other.scale,
+        }
+    }
+}
+
+/// An object moving through keyframes, interpolated linearly with the
+/// rotation slerped. It holds still before the first and after the last.
+#[derive(Clone)]
+pub struct AnimatedTransform {
+    obj: HittablePtr,
+    keyframes: Vec<Keyframe>,
+}
+
+impl AnimatedTransform {
+    /// Panics without keyframes or if a keyframe scales by zero.
+    pub fn new(obj: HittablePtr, mut keyframes: Vec<Keyframe>) -> Self {
+        assert!(!keyframes.is_empty(), "no keyframes");
+        assert!(
+            keyframes
+                .iter()
+                .all(|k| k.scale.x != 0.0 && k.scale.y != 0.0 && k.scale.z != 0.0),
+            "zero scale"
+        );
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self { obj, keyframes }
+    }
+
+    fn at(&self, time: f64) -> Keyframe {
+        let next = self.keyframes.partition_point(|k| k.time <= time);
+        match (next.checked_sub(1), self.keyframes.get(next)) {
+            (Some(i), Some(b)) => self.keyframes[i].lerp(b, time),
+            (Some(i), None) => self.keyframes[i].clone(),
+            (None, _) => self.keyframes[0].clone(),
+        }
+    }
+}
+
+impl Hittable for AnimatedTransform {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rng: &mut Random) -> Option<HitRecord> {
+        let k = self.at(r.time);
+        hit_transformed(&self.obj, &k.matrix(), &k.inverse(), r, t_min, t_max, rng)
+    }
+
+    /// Samples each keyframe interval within `[time0, time1]` and pads the
+    /// boxes by how far any point can move between two samples.
+    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        let bb = self.obj.bounding_box(time0, time1)?;
+        // Distance from the origin to the farthest corner.
+        let far = |lo: f64, hi: f64| lo.abs().max(hi.abs());
+        let radius = Vec3::new(
+            far(bb.minimum.x, bb.maximum.x),
+            far(bb.minimum.y, bb.maximum.y),
+            far(bb.minimum.z, bb.maximum.z),
+        )
+        .length();
+
+        let mut times = vec![time0];
+        times.extend(
+            self.keyframes
+                .iter()
+                .map(|k| k.time)
+                .filter(|&t| time0 < t && t < time1),
+        );
+        times.push(time1);
+
+        let mut result: Option<Aabb> = None;
+        for w in times.windows(2) {
+            let (a, b) = (self.at(w[0]), self.at(w[1]));
+            // Bound the speed of a point of the object: translation and
+            // scale change at a constant rate, and the rotation turns at a
+            // constant angular speed.
+            let max_abs = |v: &Vec3| v.x.abs().max(v.y.abs()).max(v.z.abs());
+            let max_scale = max_abs(&a.scale).max(max_abs(&b.scale));
+            let distance = (&b.translation - &a.translation).length()
+                + radius
+                    * (max_scale * a.rotation.angle_to(&b.rotation)
+                        + (&b.scale - &a.scale).length());
+            let pad = distance / (2 * BOUND_STEPS) as f64;
+            let pad = Vec3::new(pad, pad, pad);
+
+            for i in 0..=BOUND_STEPS {
+                let time = w[0] + (w[1] - w[0]) * i as f64 / BOUND_STEPS as f64;
+                let step = transformed_box(&bb, &self.at(time).matrix());
+                let step = Aabb::new(&step.minimum - &pad, &step.maximum + &pad);
+                result = Some(match result {
+                    Some(r) => r.surrounding_box(&step),
+                    None => step,
+                });
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::hittable::BoxObj;
+    use crate::material::Lambertian;
+    use crate::{Color, Point3};
+
+    #[test]
+    fn bounds_cover_the_shutter() {
+        let mat = Arc::new(Lambertian::with_color(Color::new(0.5, 0.5, 0.5)));
+        let cube: HittablePtr = Arc::new(BoxObj::new(
+            Point3::new(-1.0, -0.5, -0.5),
+            Point3::new(1.0, 0.5, 0.5),
+            mat,
+        ));
+        let axis = Vec3::new(0.3, 1.0, 0.2);
+        let animated = AnimatedTransform::new(
+            cube,
+            vec![
+                Keyframe::new(
+                    0.2,
+                    Vec3::new(0.0, 0.0, 0.0),
+                    Quat::identity(),
+                    Vec3::new(1.0, 1.0, 1.0),
+                ),
+                Keyframe::new(
+                    0.8,
+                    Vec3::new(2.0, 1.0, 0.0),
+                    Quat::from_axis_angle(&axis, 170.0),
+                    Vec3::new(1.5, 0.5, 1.0),
+                ),
+            ],
+        );
+        let bb = animated.bounding_box(0.0, 1.0).unwrap();
+        let still = animated.bounding_box(0.0, 0.1).unwrap();
+        assert!(still.maximum.x < 1.1 && bb.maximum.x > 3.0 && bb.maximum.x < 4.0);
+
+        let mut rng = Random::new(0);
+        let mut hits = 0;
+        for _ in 0..10000 {
+            let time = rng.unit_f64();
+            let orig = 10.0 * Vec3::random_unit_vector(&mut rng);
+            let target = Point3::new(1.0, 0.5, 0.0) + Vec3::random(&mut rng, -1.5, 1.5);
+            let r = Ray::new(orig.clone(), target - orig, time);
+            if let Some(rec) = animated.hit(&r, 0.001, f64::INFINITY, &mut rng) {
+                hits += 1;
+                let inside = (0..3).all(|k| bb.minimum[k] <= rec.p[k] && rec.p[k] <= bb.maximum[k]);
+                assert!(inside, "{:?} at {} outside {:?}", rec.p, time, bb);
+            }
+        }
+        assert!(hits > 1000);
+
+        // Halfway, the transform is the interpolated one.
+        let k = animated.at(0.5);
+        assert!((&k.translation - Vec3::new(1.0, 0.5, 0.0)).length() < 1e-12);
+        let expected = Quat::from_axis_angle(&axis, 85.0);
+        assert!(k.rotation.angle_to(&expected) < 1e-9);
+    }
+}
@@ -69,10 +69,50 @@ impl BoxObj {
 
 impl Hittable for BoxObj {
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rng: &mut Random) -> Option<HitRecord> {
-        self.sides.hit(r, t_min, t_max, rng)
+        let mut rec = self.sides.hit(r, t_min, t_max, rng)?;
+        // Every side faces the positive axis, so the sides at the minimum
+        // face into the box. Orient `front_face` by the outside instead.
+        let axis = (0..3)
+            .max_by(|&a, &b| rec.normal[a].abs().total_cmp(&rec.normal[b].abs()))
+            .unwrap();
+        let at_min =
+            (rec.p[axis] - self.box_min[axis]).abs() < (rec.p[axis] - self.box_max[axis]).abs();
+        let outward = if at_min { -1.0 } else { 1.0 };
+        rec.front_face = r.dir[axis] * outward < 0.0;
+        Some(rec)
     }
 
     fn bounding_box(&self, _: f64, _: f64) -> Option<Aabb> {
         Some(Aabb::new(self.box_min.clone(), self.box_max.clone()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::{Color, Vec3};
+
+    #[test]
+    fn front_face_is_outside() {
+        let mat = Arc::new(Lambertian::with_color(Color::new(0.5, 0.5, 0.5)));
+        let cube = BoxObj::new(
+            Point3::new(-1.0, -1.0, -1.0),
+            Point3::new(1.0, 1.0, 1.0),
+            mat,
+        );
+        let mut rng = Random::new(0);
+        for dir in [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        ] {
+            let entering = Ray::new(-3.0 * &dir, dir.clone(), 0.0);
+            let rec = cube.hit(&entering, 0.001, f64::INFINITY, &mut rng).unwrap();
+            assert!(rec.front_face);
+            let leaving = Ray::new(Point3::default(), dir, 0.0);
+            let rec = cube.hit(&leaving, 0.001, f64::INFINITY, &mut rng).unwrap();
+            assert!(!rec.front_face);
+        }
+    }
+}
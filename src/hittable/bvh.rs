@@ -1,110 +1,467 @@
-use std::{path::Path, sync::Arc};
-
-use anyhow::Context;
 use ordered_float::OrderedFloat;
 
-use super::{Aabb, Hittable, HittablePtr, Triangle};
-use crate::{HitRecord, MaterialPtr, Random, Vec3};
+use super::{Aabb, Hittable, HittablePtr};
+use crate::{HitRecord, Point3, Random, Ray};
 
+/// A bounding volume hierarchy over arbitrary objects.
 #[derive(Clone)]
-pub enum BvhNode {
-    Node {
-        left: Box<BvhNode>,
-        right: Box<BvhNode>,
-        bb: Aabb,
-    },
-    Leaf(HittablePtr),
+pub struct BvhNode {
+    bvh: Bvh,
+    /// In leaf order.
+    objects: Vec<HittablePtr>,
 }
 
 impl BvhNode {
-    pub fn new(
-        objects: &mut [HittablePtr],
+    /// Builds the tree with the surface area heuristic; see `Bvh::new`.
+    pub fn new(objects: &[HittablePtr], time0: f64, time1: f64) -> Option<Self> {
+        let bbs = Self::bounding_boxes(objects, time0, time1)?;
+        let (bvh, order) = Bvh::new(&bbs)?;
+        Some(Self::with_order(bvh, &order, objects))
+    }
+
+    /// The original builder: median split along a random axis, one object
+    /// per leaf.
+    pub fn random_split(
+        objects: &[HittablePtr],
         time0: f64,
         time1: f64,
         rng: &mut Random,
     ) -> Option<Self> {
-        match objects.len() {
-            0 => None,
-            1 => Some(BvhNode::Leaf(objects.first().unwrap().clone())),
-            len => {
-                match rng.range_i32(0, 3) {
-                    0 => objects.sort_by_cached_key(|o| {
-                        OrderedFloat(o.bounding_box(time0, time1).unwrap().minimum.x)
-                    }),
-                    1 => objects.sort_by_cached_key(|o| {
-                        OrderedFloat(o.bounding_box(time0, time1).unwrap().minimum.y)
-                    }),
-                    _ => objects.sort_by_cached_key(|o| {
-                        OrderedFloat(o.bounding_box(time0, time1).unwrap().minimum.z)
-                    }),
-                }
-                let (l, r) = objects.split_at_mut(len / 2);
-                let left = Self::new(l, time0, time1, rng)?;
-                let right = Self::new(r, time0, time1, rng)?;
-                let bb = left
-                    .bounding_box(time0, time1)?
-                    .surrounding_box(&right.bounding_box(time0, time1)?);
-                Some(BvhNode::Node {
-                    left: Box::new(left),
-                    right: Box::new(right),
-                    bb,
+        let bbs = Self::bounding_boxes(objects, time0, time1)?;
+        let (bvh, order) = Bvh::random_split(&bbs, rng)?;
+        Some(Self::with_order(bvh, &order, objects))
+    }
+
+    fn bounding_boxes(objects: &[HittablePtr], time0: f64, time1: f64) -> Option<Vec<Aabb>> {
+        objects
+            .iter()
+            .map(|o| o.bounding_box(time0, time1))
+            .collect()
+    }
+
+    fn with_order(bvh: Bvh, order: &[usize], objects: &[HittablePtr]) -> Self {
+        Self {
+            bvh,
+            objects: order.iter().map(|&i| objects[i].clone()).collect(),
+        }
+    }
+
+    pub fn sah_cost(&self) -> f64 {
+        self.bvh.sah_cost()
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rng: &mut Random) -> Option<HitRecord> {
+        self.bvh.hit(r, t_min, t_max, |i, t_max| {
+            self.objects[i].hit(r, t_min, t_max, rng)
+        })
+    }
+
+    fn bounding_box(&self, _time0: f64, _time1: f64) -> Option<Aabb> {
+        Some(self.bvh.bounding_box().clone())
+    }
+}
+
+/// A bounding volume hierarchy flattened into a depth-first array of nodes.
+/// It only knows primitives by position: leaves refer to contiguous index
+/// ranges of a primitive array kept by the owner in leaf order.
+#[derive(Clone)]
+pub struct Bvh {
+    nodes: Vec<LinearNode>,
+}
+
+#[derive(Clone)]
+struct LinearNode {
+    bb: Aabb,
+    kind: NodeKind,
+}
+
+#[derive(Clone)]
+enum NodeKind {
+    /// Primitives `first..first + count`.
+    Leaf { first: usize, count: usize },
+    /// Split along `axis`; the first child directly follows this node.
+    Interior { second: usize, axis: usize },
+}
+
+/// Relative costs of one box test and one primitive intersection, used both
+/// to pick splits and to score finished trees.
+const TRAVERSAL_COST: f64 = 0.125;
+const INTERSECTION_COST: f64 = 1.0;
+const SAH_BINS: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+/// Depth at which the builders stop splitting, whatever the cost, so that
+/// the traversal stack of this size cannot overflow.
+const MAX_DEPTH: usize = 64;
+
+struct Primitive {
+    index: usize,
+    bb: Aabb,
+    centroid: Point3,
+}
+
+#[derive(Default)]
+struct Bin {
+    count: usize,
+    bb: Option<Aabb>,
+}
+
+/// Collects the leaf order while the tree is being built.
+struct Builder {
+    nodes: Vec<LinearNode>,
+    order: Vec<usize>,
+    /// Depth of the node being built.
+    depth: usize,
+}
+
+impl Bvh {
+    /// Builds the tree top-down over primitives with bounding boxes `bbs`,
+    /// splitting each node at the best of `SAH_BINS - 1` candidate planes per
+    /// axis by the surface area heuristic. Up to `MAX_LEAF_SIZE` primitives
+    /// share a leaf when splitting does not pay, and any number at
+    /// `MAX_DEPTH`.
+    ///
+    /// Also returns the primitive indices in leaf order.
+    pub fn new(bbs: &[Aabb]) -> Option<(Self, Vec<usize>)> {
+        let mut prims = Self::primitives(bbs)?;
+        let mut builder = Builder::with_capacity(prims.len());
+        builder.build_sah(&mut prims);
+        Some(builder.finish())
+    }
+
+    /// Median split along a random axis, one primitive per leaf.
+    pub fn random_split(bbs: &[Aabb], rng: &mut Random) -> Option<(Self, Vec<usize>)> {
+        let mut prims = Self::primitives(bbs)?;
+        let mut builder = Builder::with_capacity(prims.len());
+        builder.build_random(&mut prims, rng);
+        Some(builder.finish())
+    }
+
+    fn primitives(bbs: &[Aabb]) -> Option<Vec<Primitive>> {
+        if bbs.is_empty() {
+            return None;
+        }
+        Some(
+            bbs.iter()
+                .enumerate()
+                .map(|(index, bb)| Primitive {
+                    index,
+                    bb: bb.clone(),
+                    centroid: bb.centroid(),
                 })
+                .collect(),
+        )
+    }
+
+    pub fn bounding_box(&self) -> &Aabb {
+        &self.nodes[0].bb
+    }
+
+    /// Expected cost of tracing a ray that hits the root box, in units of
+    /// one primitive intersection. Every node entered costs a box test and
+    /// a node is entered with probability proportional to its area.
+    pub fn sah_cost(&self) -> f64 {
+        let root_area = self.nodes[0].bb.surface_area();
+        self.nodes
+            .iter()
+            .map(|node| {
+                let count = match node.kind {
+                    NodeKind::Leaf { count, .. } => count,
+                    NodeKind::Interior { .. } => 0,
+                };
+                node.bb.surface_area() / root_area
+                    * (TRAVERSAL_COST + count as f64 * INTERSECTION_COST)
+            })
+            .sum()
+    }
+
+    /// Walks the tree front to back, calling `hit_primitive(i, t_max)` for
+    /// every primitive in a leaf the ray enters. `t_max` shrinks to the
+    /// closest hit found so far.
+    pub fn hit<F>(&self, r: &Ray, t_min: f64, t_max: f64, mut hit_primitive: F) -> Option<HitRecord>
+    where
+        F: FnMut(usize, f64) -> Option<HitRecord>,
+    {
+        let dir_is_neg = [r.dir.x < 0.0, r.dir.y < 0.0, r.dir.z < 0.0];
+        let mut closest: Option<HitRecord> = None;
+        let mut t_max = t_max;
+        let mut stack = [0; MAX_DEPTH];
+        let mut top = 0;
+        let mut index = 0;
+        loop {
+            let node = &self.nodes[index];
+            if node.bb.hit(r, t_min, t_max) {
+                match node.kind {
+                    NodeKind::Leaf { first, count } => {
+                        for i in first..first + count {
+                            if let Some(rec) = hit_primitive(i, t_max) {
+                                t_max = rec.t;
+                                closest = Some(rec);
+                            }
+                        }
+                    }
+                    NodeKind::Interior { second, axis } => {
+                        // Visit the child nearer to the ray origin first.
+                        if dir_is_neg[axis] {
+                            stack[top] = index + 1;
+                            index = second;
+                        } else {
+                            stack[top] = second;
+                            index += 1;
+                        }
+                        top += 1;
+                        continue;
+                    }
+                }
+            }
+            if top == 0 {
+                return closest;
             }
+            top -= 1;
+            index = stack[top];
         }
     }
-    pub fn load(
-        obj_file: impl AsRef<Path>,
-        time0: f64,
-        time1: f64,
-        material: MaterialPtr,
-        rng: &mut Random,
-    ) -> anyhow::Result<Self> {
-        let mut polygons: Vec<HittablePtr> = Vec::new();
+}
 
-        let ob = obj::Obj::load(obj_file)?;
-        let ob = ob.data;
-        let positions = &ob.position;
-        let polys = &ob.objects[0].groups[0].polys;
+impl Builder {
+    fn with_capacity(len: usize) -> Self {
+        Self {
+            nodes: Vec::with_capacity(2 * len),
+            order: Vec::with_capacity(len),
+            depth: 0,
+        }
+    }
+
+    fn finish(self) -> (Bvh, Vec<usize>) {
+        (Bvh { nodes: self.nodes }, self.order)
+    }
+
+    fn build_sah(&mut self, prims: &mut [Primitive]) {
+        let len = prims.len();
+        let mut bb = prims[0].bb.clone();
+        let mut centroid_bb = Aabb::new(prims[0].centroid.clone(), prims[0].centroid.clone());
+        for p in &prims[1..] {
+            bb = bb.surrounding_box(&p.bb);
+            centroid_bb =
+                centroid_bb.surrounding_box(&Aabb::new(p.centroid.clone(), p.centroid.clone()));
+        }
+        if self.depth == MAX_DEPTH {
+            return self.push_leaf(bb, prims);
+        }
 
-        for obj::SimplePolygon(poly) in polys {
-            if poly.len() != 3 {
-                todo!();
+        let leaf_cost = len as f64 * INTERSECTION_COST;
+        let area = bb.surface_area();
+        let split = Self::best_split(prims, &centroid_bb).filter(|(cost, _, _)| {
+            len > MAX_LEAF_SIZE || TRAVERSAL_COST + cost / area * INTERSECTION_COST < leaf_cost
+        });
+        match split {
+            Some((_, axis, split)) => {
+                let mut mid = 0;
+                for i in 0..len {
+                    if Self::bin_index(&prims[i], &centroid_bb, axis) < split {
+                        prims.swap(i, mid);
+                        mid += 1;
+                    }
+                }
+                self.push_interior(bb, axis, prims, mid, Self::build_sah);
+            }
+            // All centroids coincide; any partition is as good as another.
+            None if len > MAX_LEAF_SIZE => {
+                self.push_interior(bb, 0, prims, len / 2, Self::build_sah)
             }
-            let mut it = poly
-                .iter()
-                .map(|obj::IndexTuple(i, _, _)| Vec3::from(positions[*i]));
-            let p0 = it.next().unwrap();
-            let p1 = it.next().unwrap();
-            let p2 = it.next().unwrap();
-            let triangle = Triangle::new(p0, p1, p2, material.clone());
-
-            polygons.push(Arc::new(triangle));
+            None => self.push_leaf(bb, prims),
         }
+    }
 
-        Ok(Self::new(&mut polygons, time0, time1, rng).context("No bounding box")?)
+    fn build_random(&mut self, prims: &mut [Primitive], rng: &mut Random) {
+        let len = prims.len();
+        let bb = prims[1..]
+            .iter()
+            .fold(prims[0].bb.clone(), |bb, p| bb.surrounding_box(&p.bb));
+        if len == 1 || self.depth == MAX_DEPTH {
+            return self.push_leaf(bb, prims);
+        }
+        let axis = rng.range_i32(0, 3) as usize;
+        prims.sort_by_cached_key(|p| OrderedFloat(p.bb.minimum[axis]));
+        self.push_interior(bb, axis, prims, len / 2, |builder, prims| {
+            builder.build_random(prims, rng)
+        });
     }
-}
 
-impl Hittable for BvhNode {
-    fn hit(&self, r: &crate::Ray, t_min: f64, t_max: f64, rng: &mut Random) -> Option<HitRecord> {
-        match self {
-            BvhNode::Node { left, right, bb } => {
-                if !bb.hit(r, t_min, t_max) {
-                    return None;
+    fn push_leaf(&mut self, bb: Aabb, prims: &[Primitive]) {
+        let first = self.order.len();
+        self.order.extend(prims.iter().map(|p| p.index));
+        self.nodes.push(LinearNode {
+            bb,
+            kind: NodeKind::Leaf {
+                first,
+                count: prims.len(),
+            },
+        });
+    }
+
+    fn push_interior<F>(
+        &mut self,
+        bb: Aabb,
+        axis: usize,
+        prims: &mut [Primitive],
+        mid: usize,
+        mut build: F,
+    ) where
+        F: FnMut(&mut Self, &mut [Primitive]),
+    {
+        let index = self.nodes.len();
+        self.nodes.push(LinearNode {
+            bb,
+            kind: NodeKind::Interior { second: 0, axis },
+        });
+        let (l, r) = prims.split_at_mut(mid);
+        self.depth += 1;
+        build(self, l);
+        let second = self.nodes.len();
+        self.nodes[index].kind = NodeKind::Interior { second, axis };
+        build(self, r);
+        self.depth -= 1;
+    }
+
+    /// Returns the cost, the axis and the first bin of the right half
+    /// minimizing `area(L) * count(L) + area(R) * count(R)`.
+    fn best_split(prims: &[Primitive], centroid_bb: &Aabb) -> Option<(f64, usize, usize)> {
+        let mut best: Option<(f64, usize, usize)> = None;
+        for axis in 0..3 {
+            if centroid_bb.maximum[axis] - centroid_bb.minimum[axis] <= 0.0 {
+                continue;
+            }
+            let mut bins: [Bin; SAH_BINS] = Default::default();
+            for p in prims {
+                let bin = &mut bins[Self::bin_index(p, centroid_bb, axis)];
+                bin.count += 1;
+                bin.bb = p.bb.clone().merge(&bin.bb);
+            }
+
+            // right_cost[i] is the cost of the bins `i..` taken together.
+            let mut right_cost = [0.0; SAH_BINS];
+            let (mut count, mut bb) = (0, None);
+            for i in (1..SAH_BINS).rev() {
+                count += bins[i].count;
+                if let Some(b) = &bins[i].bb {
+                    bb = b.clone().merge(&bb);
+                }
+                right_cost[i] = bb.as_ref().map_or(0.0, |b: &Aabb| b.surface_area()) * count as f64;
+            }
+
+            let (mut count, mut bb) = (0, None);
+            for split in 1..SAH_BINS {
+                count += bins[split - 1].count;
+                if let Some(b) = &bins[split - 1].bb {
+                    bb = b.clone().merge(&bb);
+                }
+                if count == 0 || count == prims.len() {
+                    continue;
+                }
+                let cost = bb.as_ref().map_or(0.0, |b: &Aabb| b.surface_area()) * count as f64
+                    + right_cost[split];
+                let better = match best {
+                    Some((c, _, _)) => cost < c,
+                    None => true,
+                };
+                if better {
+                    best = Some((cost, axis, split));
                 }
-                let rec_l = left.hit(r, t_min, t_max, rng);
-                let t_max = rec_l.as_ref().map_or(t_max, |r| r.t);
-                let rec_r = right.hit(r, t_min, t_max, rng);
-                rec_r.or(rec_l)
             }
-            BvhNode::Leaf(h) => h.hit(r, t_min, t_max, rng),
         }
+        best
+    }
+
+    fn bin_index(p: &Primitive, centroid_bb: &Aabb, axis: usize) -> usize {
+        let min = centroid_bb.minimum[axis];
+        let extent = centroid_bb.maximum[axis] - min;
+        let b = ((p.centroid[axis] - min) / extent * SAH_BINS as f64) as usize;
+        b.min(SAH_BINS - 1)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
 
-    fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
-        match self {
-            BvhNode::Node { bb, .. } => Some(bb.clone()),
-            BvhNode::Leaf(h) => h.bounding_box(time0, time1),
+    use super::*;
+    use crate::hittable::{HittableList, Sphere};
+    use crate::material::Lambertian;
+    use crate::{Color, Vec3};
+
+    fn spheres(rng: &mut Random) -> Vec<HittablePtr> {
+        let mat = Arc::new(Lambertian::with_color(Color::new(0.5, 0.5, 0.5)));
+        (0..200)
+            .map(|_| {
+                let center = Point3::random(rng, -10.0, 10.0);
+                Arc::new(Sphere::new(center, rng.range_f64(0.1, 0.5), mat.clone())) as HittablePtr
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sah_beats_random_split() {
+        let mut rng = Random::new(3);
+        let objects = spheres(&mut rng);
+        let sah = BvhNode::new(&objects, 0.0, 1.0).unwrap();
+        let median = BvhNode::random_split(&objects, 0.0, 1.0, &mut rng).unwrap();
+        assert!(sah.sah_cost() < median.sah_cost());
+
+        for _ in 0..100 {
+            let r = Ray::new(
+                Point3::random(&mut rng, -15.0, 15.0),
+                Vec3::random_unit_vector(&mut rng),
+                0.0,
+            );
+            let t = |bvh: &BvhNode, rng: &mut Random| {
+                bvh.hit(&r, 0.001, f64::INFINITY, rng).map(|rec| rec.t)
+            };
+            assert_eq!(t(&sah, &mut rng), t(&median, &mut rng));
         }
     }
+
+    #[test]
+    fn flat_tree_matches_list() {
+        let mut rng = Random::new(5);
+        let objects = spheres(&mut rng);
+        let bvh = BvhNode::new(&objects, 0.0, 1.0).unwrap();
+        assert_eq!(bvh.objects.len(), objects.len());
+
+        let mut list = HittableList::default();
+        for o in &objects {
+            list.add(o.clone());
+        }
+        for _ in 0..500 {
+            let r = Ray::new(
+                Point3::random(&mut rng, -15.0, 15.0),
+                Vec3::random_unit_vector(&mut rng),
+                0.0,
+            );
+            let t_bvh = bvh.hit(&r, 0.001, f64::INFINITY, &mut rng).map(|rec| rec.t);
+            let t_list = list
+                .hit(&r, 0.001, f64::INFINITY, &mut rng)
+                .map(|rec| rec.t);
+            assert_eq!(t_bvh, t_list);
+        }
+    }
+
+    #[test]
+    fn deep_trees_are_cut_off() {
+        // Each split can only peel off the largest sphere.
+        let mat = Arc::new(Lambertian::with_color(Color::new(0.5, 0.5, 0.5)));
+        let objects: Vec<HittablePtr> = (0..1000)
+            .map(|k| {
+                let center = Point3::new(2f64.powi(k), 0.0, 0.0);
+                Arc::new(Sphere::new(center, 0.5, mat.clone())) as HittablePtr
+            })
+            .collect();
+        let bvh = BvhNode::new(&objects, 0.0, 1.0).unwrap();
+        let r = Ray::new(Point3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let rec = bvh
+            .hit(&r, 0.001, f64::INFINITY, &mut Random::new(0))
+            .unwrap();
+        assert!((rec.t - 1.5).abs() < 1e-9);
+    }
 }
@@ -1,4 +1,4 @@
-use crate::Random;
+use crate::{Point3, Random, Vec3};
 
 use super::{Aabb, HitRecord, Hittable, HittablePtr};
 
@@ -14,6 +14,9 @@ impl HittableList {
     pub fn add(&mut self, object: HittablePtr) {
         self.objects.push(object);
     }
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
 }
 
 impl Hittable for HittableList {
@@ -37,4 +40,17 @@ impl Hittable for HittableList {
         }
         bb
     }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3, rng: &mut Random) -> f64 {
+        let weight = 1.0 / self.objects.len() as f64;
+        self.objects
+            .iter()
+            .map(|object| weight * object.pdf_value(o, v, rng))
+            .sum()
+    }
+
+    fn random(&self, o: &Point3, rng: &mut Random) -> Vec3 {
+        let i = rng.range_i32(0, self.objects.len() as i32) as usize;
+        self.objects[i].random(o, rng)
+    }
 }
@@ -0,0 +1,166 @@
+use crate::algebra::Mat4;
+use crate::{MaterialPtr, Random, Ray};
+
+use super::bvh::Bvh;
+use super::transform::{hit_transformed, transformed_box};
+use super::{Aabb, HitRecord, Hittable, HittablePtr};
+
+/// One placement of shared geometry, usually a `BvhNode` or `TriangleMesh`.
+/// Copies of the `Arc` share the geometry, so an instance costs two
+/// matrices and a box, plus a material if it overrides the geometry's.
+#[derive(Clone)]
+pub struct Instance {
+    geometry: HittablePtr,
+    m: Mat4,
+    inv: Mat4,
+    bb: Option<Aabb>,
+    material: Option<MaterialPtr>,
+}
+
+impl Instance {
+    /// Panics if `m` is singular.
+    pub fn new(geometry: HittablePtr, m: Mat4) -> Self {
+        let inv = m.inverse().expect("singular transform");
+        let bb = geometry
+            .bounding_box(0.0, 1.0)
+            .map(|bb| transformed_box(&bb, &m));
+        Self {
+            geometry,
+            m,
+            inv,
+            bb,
+            material: None,
+        }
+    }
+
+    /// Shades every hit on this instance with `material` instead of the
+    /// geometry's own.
+    pub fn with_material(self, material: MaterialPtr) -> Self {
+        Self {
+            material: Some(material),
+            ..self
+        }
+    }
+}
+
+/// Top level of a two-level acceleration structure: a BVH over instances,
+/// each of which holds its own bottom-level structure.
+#[derive(Clone)]
+pub struct Instances {
+    bvh: Bvh,
+    /// Stored in leaf order of `bvh`.
+    instances: Vec<Instance>,
+}
+
+impl Instances {
+    /// Returns `None` if there are no instances or one has no bounding box.
+    pub fn new(instances: Vec<Instance>) -> Option<Self> {
+        let bbs = instances
+            .iter()
+            .map(|i| i.bb.clone())
+            .collect::<Option<Vec<_>>>()?;
+        let (bvh, order) = Bvh::new(&bbs)?;
+        let instances = order.iter().map(|&i| instances[i].clone()).collect();
+        Some(Self { bvh, instances })
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+}
+
+impl Hittable for Instances {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rng: &mut Random) -> Option<HitRecord> {
+        self.bvh.hit(r, t_min, t_max, |i, t_max| {
+            let instance = &self.instances[i];
+            let rec = hit_transformed(
+                &instance.geometry,
+                &instance.m,
+                &instance.inv,
+                r,
+                t_min,
+                t_max,
+                rng,
+            )?;
+            Some(match &instance.material {
+                Some(material) => HitRecord {
+                    mat_ptr: material.clone(),
+                    ..rec
+                },
+                None => rec,
+            })
+        })
+    }
+
+    fn bounding_box(&self, _: f64, _: f64) -> Option<Aabb> {
+        Some(self.bvh.bounding_box().clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::hittable::{transform, HittableList, Sphere};
+    use crate::material::Lambertian;
+    use crate::{Color, Point3, Vec3};
+
+    #[test]
+    fn matches_transformed_list() {
+        let mat = Arc::new(Lambertian::with_color(Color::new(0.5, 0.5, 0.5)));
+        let geometry: HittablePtr = Arc::new(Sphere::new(Point3::default(), 1.0, mat));
+
+        let mut rng = Random::new(3);
+        let mut list = HittableList::default();
+        let mut instances = Vec::new();
+        for _ in 0..200 {
+            let m = Mat4::translation(&Vec3::random(&mut rng, -20.0, 20.0))
+                * Mat4::rotation(&Vec3::random_unit_vector(&mut rng), 360.0 * rng.unit_f64())
+                * Mat4::scaling(&Vec3::random(&mut rng, 0.2, 1.0));
+            list.add(transform(geometry.clone(), m));
+            instances.push(Instance::new(geometry.clone(), m));
+        }
+        let instances = Instances::new(instances).unwrap();
+        assert_eq!(instances.len(), 200);
+        assert_eq!(Arc::strong_count(&geometry), 401);
+
+        for _ in 0..1000 {
+            let orig = 30.0 * Vec3::random_unit_vector(&mut rng);
+            let r = Ray::new(
+                orig.clone(),
+                Vec3::random(&mut rng, -20.0, 20.0) - orig,
+                0.0,
+            );
+            let a = list.hit(&r, 0.001, f64::INFINITY, &mut rng);
+            let b = instances.hit(&r, 0.001, f64::INFINITY, &mut rng);
+            assert_eq!(a.map(|a| a.t), b.map(|b| b.t));
+        }
+    }
+
+    #[test]
+    fn material_override() {
+        let mat: MaterialPtr = Arc::new(Lambertian::with_color(Color::new(0.5, 0.5, 0.5)));
+        let red: MaterialPtr = Arc::new(Lambertian::with_color(Color::new(0.8, 0.1, 0.1)));
+        let geometry: HittablePtr = Arc::new(Sphere::new(Point3::default(), 1.0, mat.clone()));
+        let left = Mat4::translation(&Vec3::new(-2.0, 0.0, 0.0));
+        let right = Mat4::translation(&Vec3::new(2.0, 0.0, 0.0));
+        let instances = Instances::new(vec![
+            Instance::new(geometry.clone(), left),
+            Instance::new(geometry, right).with_material(red.clone()),
+        ])
+        .unwrap();
+
+        let mut rng = Random::new(0);
+        let mut hit = |x: f64| {
+            let r = Ray::new(Point3::new(x, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+            instances.hit(&r, 0.001, f64::INFINITY, &mut rng).unwrap()
+        };
+        assert!(Arc::ptr_eq(&hit(-2.0).mat_ptr, &mat));
+        assert!(Arc::ptr_eq(&hit(2.0).mat_ptr, &red));
+    }
+}
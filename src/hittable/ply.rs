@@ -0,0 +1,368 @@
+use std::io::BufRead;
+
+use anyhow::{bail, Context};
+
+use super::triangle_mesh::triangulate;
+use super::MeshData;
+use crate::{Color, Point3, Vec3};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> anyhow::Result<Self> {
+        Ok(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => bail!("unknown property type `{}`", name),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    /// Value of full intensity for a color channel of this type.
+    fn full_scale(self) -> f64 {
+        match self {
+            Scalar::I8 => i8::MAX as f64,
+            Scalar::U8 => u8::MAX as f64,
+            Scalar::I16 => i16::MAX as f64,
+            Scalar::U16 => u16::MAX as f64,
+            Scalar::I32 => i32::MAX as f64,
+            Scalar::U32 => u32::MAX as f64,
+            Scalar::F32 | Scalar::F64 => 1.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Property {
+    Scalar(String, Scalar),
+    List {
+        name: String,
+        count: Scalar,
+        item: Scalar,
+    },
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+fn read_header(reader: &mut impl BufRead) -> anyhow::Result<(Format, Vec<Element>)> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if line.trim_end() != "ply" {
+        bail!("not a PLY file");
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            bail!("missing end_header");
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            ["end_header"] => break,
+            ["format", f, "1.0"] => {
+                format = Some(match f {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => bail!("unknown format `{}`", f),
+                })
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse()?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => elements
+                .last_mut()
+                .context("property before element")?
+                .properties
+                .push(Property::List {
+                    name: name.to_string(),
+                    count: Scalar::parse(count)?,
+                    item: Scalar::parse(item)?,
+                }),
+            ["property", ty, name] => elements
+                .last_mut()
+                .context("property before element")?
+                .properties
+                .push(Property::Scalar(name.to_string(), Scalar::parse(ty)?)),
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => bail!("invalid header line `{}`", line.trim_end()),
+        }
+    }
+    Ok((format.context("missing format")?, elements))
+}
+
+/// Element data after the header, read one number at a time.
+enum Body<'a> {
+    Ascii(std::str::SplitAsciiWhitespace<'a>),
+    Binary { bytes: &'a [u8], big_endian: bool },
+}
+
+impl Body<'_> {
+    fn read(&mut self, ty: Scalar) -> anyhow::Result<f64> {
+        match self {
+            Body::Ascii(tokens) => {
+                let token = tokens.next().context("unexpected end of file")?;
+                token
+                    .parse()
+                    .with_context(|| format!("invalid number `{}`", token))
+            }
+            Body::Binary { bytes, big_endian } => {
+                let size = ty.size();
+                if bytes.len() < size {
+                    bail!("unexpected end of file");
+                }
+                let mut b = [0; 8];
+                b[..size].copy_from_slice(&bytes[..size]);
+                *bytes = &bytes[size..];
+                if *big_endian {
+                    b[..size].reverse();
+                }
+                let [b0, b1, b2, b3, ..] = b;
+                Ok(match ty {
+                    Scalar::I8 => b0 as i8 as f64,
+                    Scalar::U8 => b0 as f64,
+                    Scalar::I16 => i16::from_le_bytes([b0, b1]) as f64,
+                    Scalar::U16 => u16::from_le_bytes([b0, b1]) as f64,
+                    Scalar::I32 => i32::from_le_bytes([b0, b1, b2, b3]) as f64,
+                    Scalar::U32 => u32::from_le_bytes([b0, b1, b2, b3]) as f64,
+                    Scalar::F32 => f32::from_le_bytes([b0, b1, b2, b3]) as f64,
+                    Scalar::F64 => f64::from_le_bytes(b),
+                })
+            }
+        }
+    }
+}
+
+impl MeshData {
+    /// Reads an ASCII or binary PLY file. Vertices take positions from
+    /// `x y z`, and if present normals from `nx ny nz`, texture coordinates
+    /// from `u v` or `s t`, and colors from `red green blue`. Faces are
+    /// polygons in `vertex_indices`; other elements are skipped. Vertex
+    /// normals are generated when the file has none.
+    pub fn from_ply(mut reader: impl BufRead) -> anyhow::Result<Self> {
+        let (format, elements) = read_header(&mut reader)?;
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let mut body = match format {
+            Format::Ascii => Body::Ascii(std::str::from_utf8(&bytes)?.split_ascii_whitespace()),
+            _ => Body::Binary {
+                bytes: &bytes,
+                big_endian: format == Format::BinaryBigEndian,
+            },
+        };
+
+        let mut data = MeshData::default();
+        let mut faces: Vec<Vec<usize>> = Vec::new();
+        for element in &elements {
+            // Scalar properties of the current item by name, lists by name.
+            let mut scalars = Vec::with_capacity(element.properties.len());
+            let mut lists = Vec::new();
+            let has = |name: &str| {
+                element
+                    .properties
+                    .iter()
+                    .any(|p| matches!(p, Property::Scalar(n, _) if n == name))
+            };
+            let (has_normals, has_colors) = (has("nx"), has("red"));
+            let uv_names = match (has("u"), has("s")) {
+                (true, _) => Some(("u", "v")),
+                (_, true) => Some(("s", "t")),
+                _ => None,
+            };
+
+            for _ in 0..element.count {
+                scalars.clear();
+                lists.clear();
+                for property in &element.properties {
+                    match property {
+                        Property::Scalar(name, ty) => {
+                            scalars.push((name.as_str(), *ty, body.read(*ty)?))
+                        }
+                        Property::List { name, count, item } => {
+                            let n = body.read(*count)? as usize;
+                            let values = (0..n)
+                                .map(|_| body.read(*item))
+                                .collect::<anyhow::Result<Vec<_>>>()?;
+                            lists.push((name.as_str(), values));
+                        }
+                    }
+                }
+                let get = |name: &str| {
+                    scalars
+                        .iter()
+                        .find(|(n, _, _)| *n == name)
+                        .map(|&(_, ty, x)| (ty, x))
+                        .with_context(|| format!("{} without `{}`", element.name, name))
+                };
+                let x = |name: &str| get(name).map(|(_, x)| x);
+
+                match element.name.as_str() {
+                    "vertex" => {
+                        data.positions.push(Point3::new(x("x")?, x("y")?, x("z")?));
+                        if has_normals {
+                            data.normals.push(Vec3::new(x("nx")?, x("ny")?, x("nz")?));
+                        }
+                        if let Some((u, v)) = uv_names {
+                            data.uvs.push([x(u)?, x(v)?]);
+                        }
+                        if has_colors {
+                            let channel =
+                                |name: &str| get(name).map(|(ty, value)| value / ty.full_scale());
+                            data.colors.push(Color::new(
+                                channel("red")?,
+                                channel("green")?,
+                                channel("blue")?,
+                            ));
+                        }
+                    }
+                    "face" => {
+                        let (_, indices) = lists
+                            .iter()
+                            .find(|(n, _)| *n == "vertex_indices" || *n == "vertex_index")
+                            .context("face without `vertex_indices`")?;
+                        faces.push(indices.iter().map(|&i| i as usize).collect());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let n = data.positions.len();
+        for face in faces {
+            if let Some(&i) = face.iter().find(|&&i| i >= n) {
+                bail!("vertex index {} out of range", i);
+            }
+            let points: Vec<&Point3> = face.iter().map(|&i| &data.positions[i]).collect();
+            for corners in triangulate(&points) {
+                data.indices.push(corners.map(|k| face[k] as u32));
+            }
+        }
+        if !data.uvs.is_empty() {
+            data.uv_indices = Some(data.indices.clone());
+        }
+        if data.normals.is_empty() {
+            data.generate_normals();
+        } else {
+            data.normal_indices = Some(data.indices.clone());
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "\
+ply
+format {} 1.0
+comment a unit square and a triangle
+element vertex 5
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 2
+property list uchar int vertex_indices
+end_header
+";
+
+    fn check(data: &MeshData) {
+        assert_eq!(data.positions.len(), 5);
+        assert_eq!(data.indices.len(), 3);
+        assert_eq!(data.positions[2], Point3::new(1.0, 1.0, 0.0));
+        assert_eq!(data.colors[1], Color::new(1.0, 0.0, 0.0));
+        assert_eq!(data.normals[1], Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn ascii_and_binary() -> anyhow::Result<()> {
+        let vertices = [
+            ([0.0, 0.0, 0.0], [0, 0, 0]),
+            ([1.0, 0.0, 0.0], [255, 0, 0]),
+            ([1.0, 1.0, 0.0], [0, 255, 0]),
+            ([0.0, 1.0, 0.0], [0, 0, 255]),
+            ([0.0, 0.0, 1.0], [255, 255, 255]),
+        ];
+        let faces: [&[i32]; 2] = [&[0, 1, 2, 3], &[0, 3, 4]];
+
+        let mut ascii = HEADER.replace("{}", "ascii");
+        for (p, c) in &vertices {
+            ascii += &format!("{} {} {} {} {} {}\n", p[0], p[1], p[2], c[0], c[1], c[2]);
+        }
+        for f in &faces {
+            let indices: Vec<String> = f.iter().map(|i| i.to_string()).collect();
+            ascii += &format!("{} {}\n", f.len(), indices.join(" "));
+        }
+        check(&MeshData::from_ply(ascii.as_bytes())?);
+
+        for (format, big_endian) in [("binary_little_endian", false), ("binary_big_endian", true)] {
+            let mut binary = HEADER.replace("{}", format).into_bytes();
+            for (p, c) in &vertices {
+                for &x in p {
+                    let x = x as f32;
+                    binary.extend(if big_endian {
+                        x.to_be_bytes()
+                    } else {
+                        x.to_le_bytes()
+                    });
+                }
+                binary.extend(c.map(|c: i32| c as u8));
+            }
+            for f in &faces {
+                binary.push(f.len() as u8);
+                for &i in f.iter() {
+                    binary.extend(if big_endian {
+                        i.to_be_bytes()
+                    } else {
+                        i.to_le_bytes()
+                    });
+                }
+            }
+            check(&MeshData::from_ply(&binary[..])?);
+        }
+        Ok(())
+    }
+}
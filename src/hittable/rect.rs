@@ -57,6 +57,20 @@ impl Hittable for XYRect {
             Point3::new(self.x1, self.y1, self.k + EPS),
         ))
     }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3, rng: &mut Random) -> f64 {
+        let area = (self.x1 - self.x0) * (self.y1 - self.y0);
+        rect_pdf_value(self, area, o, v, rng)
+    }
+
+    fn random(&self, o: &Point3, rng: &mut Random) -> Vec3 {
+        let p = Point3::new(
+            rng.range_f64(self.x0, self.x1),
+            rng.range_f64(self.y0, self.y1),
+            self.k,
+        );
+        p - o
+    }
 }
 
 #[derive(Clone)]
@@ -112,6 +126,20 @@ impl Hittable for XZRect {
             Point3::new(self.x1, self.k + EPS, self.z1),
         ))
     }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3, rng: &mut Random) -> f64 {
+        let area = (self.x1 - self.x0) * (self.z1 - self.z0);
+        rect_pdf_value(self, area, o, v, rng)
+    }
+
+    fn random(&self, o: &Point3, rng: &mut Random) -> Vec3 {
+        let p = Point3::new(
+            rng.range_f64(self.x0, self.x1),
+            self.k,
+            rng.range_f64(self.z0, self.z1),
+        );
+        p - o
+    }
 }
 
 #[derive(Clone)]
@@ -167,4 +195,34 @@ impl Hittable for YZRect {
             Point3::new(self.k + EPS, self.y1, self.z1),
         ))
     }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3, rng: &mut Random) -> f64 {
+        let area = (self.y1 - self.y0) * (self.z1 - self.z0);
+        rect_pdf_value(self, area, o, v, rng)
+    }
+
+    fn random(&self, o: &Point3, rng: &mut Random) -> Vec3 {
+        let p = Point3::new(
+            self.k,
+            rng.range_f64(self.y0, self.y1),
+            rng.range_f64(self.z0, self.z1),
+        );
+        p - o
+    }
+}
+
+fn rect_pdf_value(rect: &impl Hittable, area: f64, o: &Point3, v: &Vec3, rng: &mut Random) -> f64 {
+    match rect.hit(
+        &Ray::new(o.clone(), v.clone(), 0.0),
+        0.001,
+        f64::INFINITY,
+        rng,
+    ) {
+        Some(rec) => {
+            let distance_squared = rec.t.powi(2) * v.length_squared();
+            let cosine = (v.dot(&rec.normal) / v.length()).abs();
+            distance_squared / (cosine * area)
+        }
+        None => 0.0,
+    }
 }
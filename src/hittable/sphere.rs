@@ -1,4 +1,5 @@
-use crate::{Point3, Random, Vec3};
+use crate::onb::Onb;
+use crate::{Point3, Random, Ray, Vec3};
 
 use super::{Aabb, HitRecord, Hittable, MaterialPtr};
 
@@ -28,6 +29,54 @@ impl Hittable for Sphere {
         let v = Vec3::new(self.radius, self.radius, self.radius);
         Some(Aabb::new(&self.center - &v, &self.center + &v))
     }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3, rng: &mut Random) -> f64 {
+        if self
+            .hit(
+                &Ray::new(o.clone(), v.clone(), 0.0),
+                0.001,
+                f64::INFINITY,
+                rng,
+            )
+            .is_none()
+        {
+            return 0.0;
+        }
+        let distance_squared = (&self.center - o).length_squared();
+        if distance_squared <= self.radius.powi(2) {
+            // From inside, every direction leads to the surface.
+            return 1.0 / (4.0 * std::f64::consts::PI);
+        }
+        let cos_theta_max = (1.0 - self.radius.powi(2) / distance_squared).sqrt();
+        let solid_angle = std::f64::consts::TAU * (1.0 - cos_theta_max);
+        1.0 / solid_angle
+    }
+
+    fn random(&self, o: &Point3, rng: &mut Random) -> Vec3 {
+        let direction = &self.center - o;
+        if direction.length_squared() <= self.radius.powi(2) {
+            return Vec3::random_unit_vector(rng);
+        }
+        let uvw = Onb::build_from_w(&direction);
+        uvw.local(&random_to_sphere(
+            self.radius,
+            direction.length_squared(),
+            rng,
+        ))
+    }
+}
+
+/// Direction inside the cone subtended by a sphere, in local coordinates.
+fn random_to_sphere(radius: f64, distance_squared: f64, rng: &mut Random) -> Vec3 {
+    let r1 = rng.unit_f64();
+    let r2 = rng.unit_f64();
+    let z = 1.0 + r2 * ((1.0 - radius.powi(2) / distance_squared).sqrt() - 1.0);
+
+    let phi = std::f64::consts::TAU * r1;
+    let x = phi.cos() * (1.0 - z * z).sqrt();
+    let y = phi.sin() * (1.0 - z * z).sqrt();
+
+    Vec3::new(x, y, z)
 }
 
 #[derive(Clone)]
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use crate::algebra::Mat4;
 use crate::{Point3, Random, Ray, Vec3};
 
 use super::{Aabb, HitRecord, Hittable, HittablePtr};
@@ -30,6 +31,14 @@ impl Hittable for Translate {
         let Aabb { minimum, maximum } = self.obj.bounding_box(time0, time1)?;
         Some(Aabb::new(minimum + &self.offset, maximum + &self.offset))
     }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3, rng: &mut Random) -> f64 {
+        self.obj.pdf_value(&(o - &self.offset), v, rng)
+    }
+
+    fn random(&self, o: &Point3, rng: &mut Random) -> Vec3 {
+        self.obj.random(&(o - &self.offset), rng)
+    }
 }
 
 #[derive(Clone)]
@@ -107,6 +116,97 @@ impl Hittable for RotateY {
     }
 }
 
+/// An object under an invertible affine transform from its own space to
+/// the world.
+#[derive(Clone)]
+pub struct Transform {
+    obj: HittablePtr,
+    m: Mat4,
+    inv: Mat4,
+    bbox: Option<Aabb>,
+}
+
+impl Transform {
+    /// Panics if `m` is singular.
+    pub fn new(obj: HittablePtr, m: Mat4) -> Self {
+        let inv = m.inverse().expect("singular transform");
+        let bbox = obj
+            .bounding_box(0.0, 1.0)
+            .map(|bb| transformed_box(&bb, &m));
+
+        Self { obj, m, inv, bbox }
+    }
+
+    pub fn matrix(&self) -> &Mat4 {
+        &self.m
+    }
+}
+
+impl Hittable for Transform {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, rng: &mut Random) -> Option<HitRecord> {
+        hit_transformed(&self.obj, &self.m, &self.inv, r, t_min, t_max, rng)
+    }
+
+    fn bounding_box(&self, _: f64, _: f64) -> Option<Aabb> {
+        self.bbox.clone()
+    }
+
+    fn pdf_value(&self, o: &Point3, v: &Vec3, rng: &mut Random) -> f64 {
+        let local_v = self.inv.transform_vector(v);
+        let pdf = self
+            .obj
+            .pdf_value(&self.inv.transform_point(o), &local_v, rng);
+        // Jacobian of the map between directions, |det m| / |m d|^3 for a
+        // unit local direction d.
+        pdf * (v.length() / local_v.length()).powi(3) / self.m.det3().abs()
+    }
+
+    fn random(&self, o: &Point3, rng: &mut Random) -> Vec3 {
+        let local = self.obj.random(&self.inv.transform_point(o), rng);
+        self.m.transform_vector(&local)
+    }
+}
+
+/// Box around the image of `bb` under `m`.
+pub(super) fn transformed_box(bb: &Aabb, m: &Mat4) -> Aabb {
+    let corner = |x: f64, y: f64, z: f64| {
+        let p = m.transform_point(&Point3::new(x, y, z));
+        Aabb::new(p.clone(), p)
+    };
+    let (lo, hi) = (&bb.minimum, &bb.maximum);
+    [lo.x, hi.x]
+        .iter()
+        .flat_map(|&x| [(x, lo.y), (x, hi.y)])
+        .flat_map(|(x, y)| [corner(x, y, lo.z), corner(x, y, hi.z)])
+        .reduce(|a, b| a.surrounding_box(&b))
+        .unwrap()
+}
+
+/// Hits `obj` in the space that `m`, with inverse `inv`, maps to the world.
+pub(super) fn hit_transformed(
+    obj: &HittablePtr,
+    m: &Mat4,
+    inv: &Mat4,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+    rng: &mut Random,
+) -> Option<HitRecord> {
+    // The direction is not renormalized, so `t` is the same in both spaces.
+    let local_r = Ray::new(
+        inv.transform_point(&r.orig),
+        inv.transform_vector(&r.dir),
+        r.time,
+    );
+    let rec = obj.hit(&local_r, t_min, t_max, rng)?;
+    Some(HitRecord {
+        p: m.transform_point(&rec.p),
+        // The inverse transpose keeps normals perpendicular to the surface.
+        normal: inv.transform_vector_transposed(&rec.normal).unit_vector(),
+        ..rec
+    })
+}
+
 pub fn translate(obj: HittablePtr, offset: Vec3) -> HittablePtr {
     Arc::new(Translate::new(obj, offset))
 }
@@ -114,3 +214,117 @@ pub fn translate(obj: HittablePtr, offset: Vec3) -> HittablePtr {
 pub fn rotate_y(obj: HittablePtr, theta: f64) -> HittablePtr {
     Arc::new(RotateY::new(obj, theta))
 }
+
+pub fn transform(obj: HittablePtr, m: Mat4) -> HittablePtr {
+    Arc::new(Transform::new(obj, m))
+}
+
+pub fn rotate_x(obj: HittablePtr, theta: f64) -> HittablePtr {
+    transform(obj, Mat4::rotation(&Vec3::new(1.0, 0.0, 0.0), theta))
+}
+
+pub fn rotate_z(obj: HittablePtr, theta: f64) -> HittablePtr {
+    transform(obj, Mat4::rotation(&Vec3::new(0.0, 0.0, 1.0), theta))
+}
+
+/// Rotates by `theta` degrees counter-clockwise looking down `axis`.
+pub fn rotate_axis(obj: HittablePtr, axis: &Vec3, theta: f64) -> HittablePtr {
+    transform(obj, Mat4::rotation(axis, theta))
+}
+
+/// Scales about the origin; panics if a factor is zero.
+pub fn scale(obj: HittablePtr, factor: &Vec3) -> HittablePtr {
+    transform(obj, Mat4::scaling(factor))
+}
+
+/// Places the object's origin at `from`, facing `at` along its -z axis.
+pub fn look_at(obj: HittablePtr, from: &Point3, at: &Point3, up: &Vec3) -> HittablePtr {
+    transform(obj, Mat4::look_at(from, at, up))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::{BoxObj, Sphere};
+    use crate::material::Lambertian;
+    use crate::Color;
+
+    fn unit_sphere() -> HittablePtr {
+        let mat = Arc::new(Lambertian::with_color(Color::new(0.5, 0.5, 0.5)));
+        Arc::new(Sphere::new(Point3::default(), 1.0, mat))
+    }
+
+    #[test]
+    fn matches_rotate_y() {
+        let mat = Arc::new(Lambertian::with_color(Color::new(0.5, 0.5, 0.5)));
+        let cube: HittablePtr = Arc::new(BoxObj::new(
+            Point3::new(-1.0, -1.0, -2.0),
+            Point3::new(1.0, 2.0, 1.0),
+            mat,
+        ));
+        let general = rotate_axis(cube.clone(), &Vec3::new(0.0, 1.0, 0.0), 30.0);
+        let special = rotate_y(cube, 30.0);
+        let (a, b) = (
+            general.bounding_box(0.0, 1.0),
+            special.bounding_box(0.0, 1.0),
+        );
+        let (a, b) = (a.unwrap(), b.unwrap());
+        assert!((&a.minimum - &b.minimum).length() < 1e-9);
+        assert!((&a.maximum - &b.maximum).length() < 1e-9);
+
+        let mut rng = Random::new(0);
+        for _ in 0..100 {
+            let orig = 5.0 * Vec3::random_unit_vector(&mut rng);
+            let r = Ray::new(orig.clone(), -orig, 0.0);
+            let (a, b) = (
+                general.hit(&r, 0.001, f64::INFINITY, &mut rng).unwrap(),
+                special.hit(&r, 0.001, f64::INFINITY, &mut rng).unwrap(),
+            );
+            assert!((a.t - b.t).abs() < 1e-9);
+            assert!((&a.normal - &b.normal).length() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn ellipsoid_normals_and_pdf() {
+        let m = Mat4::rotation(&Vec3::new(1.0, 1.0, 0.0), 40.0)
+            * Mat4::scaling(&Vec3::new(2.0, 0.5, 1.0));
+        let ellipsoid = Transform::new(unit_sphere(), m);
+        let bb = ellipsoid.bounding_box(0.0, 1.0).unwrap();
+
+        // The normal of the surface x^T A x = 1 is along A x.
+        let a = ellipsoid.inv.transpose() * ellipsoid.inv;
+        let mut rng = Random::new(1);
+        let o = Point3::new(0.3, 3.0, -0.2);
+        let rec = ellipsoid
+            .hit(
+                &Ray::new(o.clone(), -&o, 0.0),
+                0.001,
+                f64::INFINITY,
+                &mut rng,
+            )
+            .unwrap();
+        let expected = a.transform_vector(&rec.p).unit_vector();
+        assert!((&rec.normal - expected).length() < 1e-9);
+
+        // Sampled directions hit the ellipsoid inside its bounding box, and
+        // the density integrates to one over the sphere of directions. The
+        // integral is estimated over the cone of a sphere enclosing it.
+        let mat = Arc::new(Lambertian::with_color(Color::new(0.5, 0.5, 0.5)));
+        let enclosing = Sphere::new(Point3::default(), 2.0, mat);
+        let n = 200_000;
+        let mut integral = 0.0;
+        for _ in 0..n {
+            let v = ellipsoid.random(&o, &mut rng);
+            let rec = ellipsoid
+                .hit(&Ray::new(o.clone(), v, 0.0), 0.001, f64::INFINITY, &mut rng)
+                .unwrap();
+            assert!((0..3).all(|k| bb.minimum[k] <= rec.p[k] && rec.p[k] <= bb.maximum[k]));
+            let u = enclosing.random(&o, &mut rng);
+            integral +=
+                ellipsoid.pdf_value(&o, &u, &mut rng) / enclosing.pdf_value(&o, &u, &mut rng);
+        }
+        integral /= n as f64;
+        assert!((integral - 1.0).abs() < 0.02, "{}", integral);
+    }
+}
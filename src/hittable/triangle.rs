@@ -1,14 +1,14 @@
-use crate::{algebra::solve_equation, MaterialPtr, Point3, Random, Ray, Vec3};
+use crate::{MaterialPtr, Point3, Random, Ray, Vec3};
 
 use super::{Aabb, HitRecord, Hittable};
 
-const EPS: f64 = 0.0001;
+pub(crate) const EPS: f64 = 0.0001;
 
 #[derive(Clone)]
 pub struct Triangle {
     p0: Point3,
-    a: Vec3,
-    b: Vec3,
+    p1: Point3,
+    p2: Point3,
     normal: Vec3,
     material: MaterialPtr,
     bb: Aabb,
@@ -16,21 +16,19 @@ pub struct Triangle {
 
 impl Triangle {
     pub fn new(p0: Point3, p1: Vec3, p2: Vec3, material: MaterialPtr) -> Self {
-        let a = &p1 - &p0;
-        let b = &p2 - &p0;
-        let normal = a.cross(&b).unit_vector();
+        let normal = (&p1 - &p0).cross(&(&p2 - &p0)).unit_vector();
 
         let b0 = Aabb::new(p0.clone(), p0.clone());
-        let b1 = Aabb::new(p1.clone(), p1);
-        let b2 = Aabb::new(p2.clone(), p2);
+        let b1 = Aabb::new(p1.clone(), p1.clone());
+        let b2 = Aabb::new(p2.clone(), p2.clone());
 
         let mut bb = b0.surrounding_box(&b1).surrounding_box(&b2);
         bb.wrap(EPS);
 
         Self {
             p0,
-            a,
-            b,
+            p1,
+            p2,
             normal,
             material,
             bb,
@@ -38,21 +36,74 @@ impl Triangle {
     }
 }
 
+/// Watertight ray/triangle intersection (Woop, Benthin and Wald 2013).
+/// Returns `(t, u, v)` with the hit point at `(1 - u - v) p0 + u p1 + v p2`.
+///
+/// The vertices are transformed into a ray-aligned frame where the edge
+/// tests only depend on the edge's own endpoints, so a ray crossing an edge
+/// shared by two triangles hits at least one of them.
+pub(crate) fn intersect(
+    p0: &Point3,
+    p1: &Point3,
+    p2: &Point3,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, f64, f64)> {
+    let dir = &r.dir;
+    // Make z the dominant axis of the ray direction, keeping the winding.
+    let kz = if dir.x.abs() > dir.y.abs() {
+        if dir.x.abs() > dir.z.abs() {
+            0
+        } else {
+            2
+        }
+    } else if dir.y.abs() > dir.z.abs() {
+        1
+    } else {
+        2
+    };
+    let (kx, ky) = if dir[kz] < 0.0 {
+        ((kz + 2) % 3, (kz + 1) % 3)
+    } else {
+        ((kz + 1) % 3, (kz + 2) % 3)
+    };
+    let sx = dir[kx] / dir[kz];
+    let sy = dir[ky] / dir[kz];
+    let sz = 1.0 / dir[kz];
+
+    // Vertices relative to the origin, sheared so the ray runs along +z.
+    let shear = |p: &Point3| {
+        let d = p - &r.orig;
+        (d[kx] - sx * d[kz], d[ky] - sy * d[kz], sz * d[kz])
+    };
+    let (ax, ay, az) = shear(p0);
+    let (bx, by, bz) = shear(p1);
+    let (cx, cy, cz) = shear(p2);
+
+    // Scaled barycentric coordinates from 2D edge functions.
+    let e0 = cx * by - cy * bx;
+    let e1 = ax * cy - ay * cx;
+    let e2 = bx * ay - by * ax;
+    if (e0 < 0.0 || e1 < 0.0 || e2 < 0.0) && (e0 > 0.0 || e1 > 0.0 || e2 > 0.0) {
+        return None;
+    }
+    let det = e0 + e1 + e2;
+    if det == 0.0 {
+        // Parallel to the triangle, or a degenerate triangle.
+        return None;
+    }
+
+    let t = (e0 * az + e1 * bz + e2 * cz) / det;
+    if !(t_min..=t_max).contains(&t) {
+        return None;
+    }
+    Some((t, e1 / det, e2 / det))
+}
+
 impl Hittable for Triangle {
     fn hit(&self, r: &Ray, t_min: f64, t_max: f64, _: &mut Random) -> Option<HitRecord> {
-        let coef = Vec3::to_matrix(&r.dir, &-&self.a, &-&self.b);
-        let rhs = &self.p0 - &r.orig;
-        let rhs = rhs.into();
-        let tuv = solve_equation(coef, rhs);
-        let t = tuv[0];
-        if t < t_min || t > t_max {
-            return None;
-        }
-        let u = tuv[1];
-        let v = tuv[2];
-        if u < 0.0 || v < 0.0 || u + v > 1.0 {
-            return None;
-        }
+        let (t, u, v) = intersect(&self.p0, &self.p1, &self.p2, r, t_min, t_max)?;
         Some(HitRecord::new(
             r.at(t),
             t,
@@ -68,3 +119,48 @@ impl Hittable for Triangle {
         Some(self.bb.clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn barycentric_coordinates() {
+        let (p0, p1, p2) = (
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(2.0, 0.0, 0.0),
+            Point3::new(0.0, 2.0, 0.0),
+        );
+        let r = Ray::new(Point3::new(0.5, 1.0, -3.0), Vec3::new(0.0, 0.0, 2.0), 0.0);
+        let (t, u, v) = intersect(&p0, &p1, &p2, &r, 0.0, f64::INFINITY).unwrap();
+        assert!((t - 1.5).abs() < 1e-12);
+        assert!((u - 0.25).abs() < 1e-12 && (v - 0.5).abs() < 1e-12);
+
+        let parallel = Ray::new(Point3::new(0.5, 1.0, 0.0), Vec3::new(1.0, 1.0, 0.0), 0.0);
+        assert!(intersect(&p0, &p1, &p2, &parallel, 0.0, f64::INFINITY).is_none());
+        let degenerate = intersect(&p0, &p1, &p1, &r, 0.0, f64::INFINITY);
+        assert!(degenerate.is_none());
+    }
+
+    #[test]
+    fn shared_edge_is_watertight() {
+        let mut rng = Random::new(11);
+        let quad = [
+            Point3::new(-1.3, 0.2, 0.7),
+            Point3::new(1.1, -0.4, 0.3),
+            Point3::new(0.9, 1.7, -0.2),
+            Point3::new(-0.8, 1.3, 0.1),
+        ];
+        for _ in 0..10000 {
+            // Aim at a point on the diagonal shared by both triangles.
+            let s = rng.unit_f64();
+            let target = (1.0 - s) * &quad[0] + s * &quad[2];
+            let orig = Vec3::random(&mut rng, -5.0, 5.0) + Vec3::new(0.0, 0.0, 10.0);
+            let r = Ray::new(orig.clone(), target - orig, 0.0);
+            let hit = |a: &Point3, b: &Point3, c: &Point3| {
+                intersect(a, b, c, &r, 0.0, f64::INFINITY).is_some()
+            };
+            assert!(hit(&quad[0], &quad[1], &quad[2]) || hit(&quad[0], &quad[2], &quad[3]));
+        }
+    }
+}
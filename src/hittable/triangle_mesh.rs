@@ -0,0 +1,578 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+
+use super::bvh::Bvh;
+use super::triangle::{intersect, EPS};
+use super::{Aabb, HitRecord, Hittable};
+use super::{BvhNode, HittablePtr};
+use crate::material::{from_mtl, Lambertian};
+use crate::{Color, MaterialPtr, Point3, Random, Ray, Vec3};
+
+/// Vertex buffers of a triangle mesh. Each triangle indexes positions, and
+/// optionally normals and texture coordinates, separately as OBJ does.
+#[derive(Debug, Default, Clone)]
+pub struct MeshData {
+    pub positions: Vec<Point3>,
+    /// Per-position colors, empty if the mesh has none.
+    pub colors: Vec<Color>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<[f64; 2]>,
+    pub indices: Vec<[u32; 3]>,
+    pub normal_indices: Option<Vec<[u32; 3]>>,
+    pub uv_indices: Option<Vec<[u32; 3]>>,
+}
+
+impl MeshData {
+    /// Triangulates `polys` of `ob`, copying only the vertices they use.
+    /// Normals and texture coordinates are kept only if every polygon has
+    /// them.
+    pub fn from_obj<'a>(
+        ob: &obj::ObjData,
+        polys: impl IntoIterator<Item = &'a obj::SimplePolygon>,
+    ) -> Self {
+        let (mut positions, mut normals, mut uvs) = (Vec::new(), Vec::new(), Vec::new());
+        let (mut position_map, mut normal_map, mut uv_map) =
+            (HashMap::new(), HashMap::new(), HashMap::new());
+        let mut indices = Vec::new();
+        let mut normal_indices = Vec::new();
+        let mut uv_indices = Vec::new();
+        for obj::SimplePolygon(poly) in polys {
+            let points: Vec<Point3> = poly.iter().map(|t| Vec3::from(ob.position[t.0])).collect();
+            for corners in triangulate(&points.iter().collect::<Vec<_>>()) {
+                let [a, b, c] = corners.map(|k| &poly[k]);
+                indices.push(
+                    [a.0, b.0, c.0]
+                        .map(|i| compact(&mut position_map, &mut positions, &ob.position, i)),
+                );
+                if let (Some(a), Some(b), Some(c)) = (a.1, b.1, c.1) {
+                    uv_indices
+                        .push([a, b, c].map(|i| compact(&mut uv_map, &mut uvs, &ob.texture, i)));
+                }
+                if let (Some(a), Some(b), Some(c)) = (a.2, b.2, c.2) {
+                    normal_indices.push(
+                        [a, b, c].map(|i| compact(&mut normal_map, &mut normals, &ob.normal, i)),
+                    );
+                }
+            }
+        }
+
+        let mut data = MeshData {
+            positions: positions.into_iter().map(Vec3::from).collect(),
+            ..Default::default()
+        };
+        if uv_indices.len() == indices.len() {
+            data.uvs = uvs.into_iter().map(|[u, v]| [u as f64, v as f64]).collect();
+            data.uv_indices = Some(uv_indices);
+        }
+        data.indices = indices;
+        if normal_indices.len() == data.indices.len() {
+            data.normals = normals.into_iter().map(Vec3::from).collect();
+            data.normal_indices = Some(normal_indices);
+        } else {
+            data.generate_normals();
+        }
+        data
+    }
+
+    /// Loads a PLY or STL file by extension, or else every polygon of every
+    /// object and group in an OBJ file. Vertex normals are generated when an
+    /// OBJ or PLY file has none.
+    pub fn load(file: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = file.as_ref();
+        let extension = path.extension().and_then(|e| e.to_str());
+        Ok(match extension.map(str::to_ascii_lowercase).as_deref() {
+            Some("ply") => MeshData::from_ply(BufReader::new(File::open(path)?))?,
+            Some("stl") => MeshData::from_stl(&mut BufReader::new(File::open(path)?))?,
+            _ => {
+                let ob = obj::Obj::load(path)?.data;
+                let polys = ob
+                    .objects
+                    .iter()
+                    .flat_map(|o| &o.groups)
+                    .flat_map(|g| &g.polys);
+                MeshData::from_obj(&ob, polys)
+            }
+        })
+    }
+
+    /// Reads an ASCII or binary STL file. STL stores facet normals only, so
+    /// the mesh is shaded flat.
+    pub fn from_stl(reader: &mut (impl Read + Seek)) -> anyhow::Result<Self> {
+        let stl = stl_io::read_stl(reader)?;
+        Ok(MeshData {
+            positions: stl.vertices.iter().map(|v| Vec3::from(v.0)).collect(),
+            indices: stl
+                .faces
+                .iter()
+                .map(|f| f.vertices.map(|i| i as u32))
+                .collect(),
+            ..Default::default()
+        })
+    }
+
+    /// Replaces the normals with per-position normals averaging the normals
+    /// of adjacent faces, each weighted by the face's angle at the vertex so
+    /// the result does not depend on how the surface is triangulated.
+    pub fn generate_normals(&mut self) {
+        let mut normals = vec![Vec3::default(); self.positions.len()];
+        for &[i0, i1, i2] in &self.indices {
+            let p = [i0, i1, i2].map(|i| &self.positions[i as usize]);
+            let face = (p[1] - p[0]).cross(&(p[2] - p[0]));
+            if face.near_zero() {
+                continue;
+            }
+            let face = face.unit_vector();
+            for (k, &i) in [i0, i1, i2].iter().enumerate() {
+                let e0 = (p[(k + 1) % 3] - p[k]).unit_vector();
+                let e1 = (p[(k + 2) % 3] - p[k]).unit_vector();
+                let angle = e0.dot(&e1).clamp(-1.0, 1.0).acos();
+                normals[i as usize] += angle * &face;
+            }
+        }
+        self.normals = normals
+            .into_iter()
+            .map(|n| if n.near_zero() { n } else { n.unit_vector() })
+            .collect();
+        self.normal_indices = Some(self.indices.clone());
+    }
+}
+
+/// Triangles sharing vertex buffers, with their own BVH. Costs 12 bytes of
+/// indices per triangle on top of the vertices and the tree.
+#[derive(Clone)]
+pub struct TriangleMesh {
+    /// Triangles are stored in leaf order of `bvh`.
+    data: MeshData,
+    /// `None` shades with the vertex colors.
+    material: Option<MaterialPtr>,
+    bvh: Bvh,
+}
+
+impl TriangleMesh {
+    /// Returns `None` for a mesh without triangles.
+    pub fn new(data: MeshData, material: MaterialPtr) -> Option<Self> {
+        Self::build(data, Some(material))
+    }
+
+    /// Shades the mesh diffusely with its vertex colors, interpolated
+    /// across each triangle. Returns `None` for a mesh without triangles or
+    /// without colors.
+    pub fn with_vertex_colors(data: MeshData) -> Option<Self> {
+        if data.colors.is_empty() {
+            return None;
+        }
+        Self::build(data, None)
+    }
+
+    fn build(mut data: MeshData, material: Option<MaterialPtr>) -> Option<Self> {
+        let bbs: Vec<Aabb> = data
+            .indices
+            .iter()
+            .map(|[i0, i1, i2]| {
+                let p = |i: &u32| &data.positions[*i as usize];
+                let mut bb = Aabb::new(p(i0).clone(), p(i0).clone())
+                    .surrounding_box(&Aabb::new(p(i1).clone(), p(i1).clone()))
+                    .surrounding_box(&Aabb::new(p(i2).clone(), p(i2).clone()));
+                bb.wrap(EPS);
+                bb
+            })
+            .collect();
+        let (bvh, order) = Bvh::new(&bbs)?;
+
+        data.indices = reorder(&data.indices, &order);
+        data.normal_indices = data.normal_indices.map(|n| reorder(&n, &order));
+        data.uv_indices = data.uv_indices.map(|uv| reorder(&uv, &order));
+        Some(Self {
+            data,
+            material,
+            bvh,
+        })
+    }
+
+    /// Loads a mesh file as `MeshData::load` does.
+    pub fn load(file: impl AsRef<Path>, material: MaterialPtr) -> anyhow::Result<Self> {
+        Self::new(MeshData::load(file)?, material).context("No triangles")
+    }
+
+    pub fn from_obj(ob: &obj::ObjData, material: MaterialPtr) -> anyhow::Result<Self> {
+        let polys = ob
+            .objects
+            .iter()
+            .flat_map(|o| &o.groups)
+            .flat_map(|g| &g.polys);
+        let data = MeshData::from_obj(ob, polys);
+        Self::new(data, material).context("No triangles")
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.indices.is_empty()
+    }
+
+    fn hit_triangle(&self, i: usize, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let data = &self.data;
+        let [p0, p1, p2] = data.indices[i].map(|j| &data.positions[j as usize]);
+        let (t, u, v) = intersect(p0, p1, p2, r, t_min, t_max)?;
+
+        // Barycentric weights of the three vertices.
+        let w = [1.0 - u - v, u, v];
+        let (tex_u, tex_v) = match &data.uv_indices {
+            Some(uv_indices) => uv_indices[i]
+                .iter()
+                .zip(&w)
+                .map(|(&j, w)| {
+                    let [s, t] = data.uvs[j as usize];
+                    (w * s, w * t)
+                })
+                .fold((0.0, 0.0), |(s0, t0), (s, t)| (s0 + s, t0 + t)),
+            None => (u, v),
+        };
+        let material: MaterialPtr = match &self.material {
+            Some(material) => material.clone(),
+            None => {
+                let color = data.indices[i]
+                    .iter()
+                    .zip(&w)
+                    .fold(Color::default(), |c, (&j, w)| {
+                        c + *w * &data.colors[j as usize]
+                    });
+                Arc::new(Lambertian::with_color(color))
+            }
+        };
+
+        let mut rec = HitRecord::new(
+            r.at(t),
+            t,
+            tex_u,
+            tex_v,
+            r,
+            (p1 - p0).cross(&(p2 - p0)).unit_vector(),
+            material,
+        );
+        if let Some(normal_indices) = &data.normal_indices {
+            let n = normal_indices[i]
+                .iter()
+                .zip(&w)
+                .fold(Vec3::default(), |n, (&j, w)| {
+                    n + *w * &data.normals[j as usize]
+                })
+                .unit_vector();
+            // Keep the shading normal on the side the ray came from.
+            rec.normal = if n.dot(&rec.normal) < 0.0 { -n } else { n };
+        }
+        Some(rec)
+    }
+}
+
+/// Loads an OBJ file together with its MTL libraries, with one mesh per
+/// material. Groups without `usemtl`, or naming a material missing from
+/// the libraries, get `default_material`; so does every group whose
+/// library fails to load, which is reported on stderr.
+pub fn load_obj(
+    obj_file: impl AsRef<Path>,
+    default_material: MaterialPtr,
+) -> anyhow::Result<BvhNode> {
+    let path = obj_file.as_ref();
+    let mut ob = obj::Obj::load(path)?;
+    if let Err(obj::MtlLibsLoadError(errs)) = ob.load_mtls() {
+        for (lib, err) in errs {
+            eprintln!(
+                "{}: skipping material library {}: {}",
+                path.display(),
+                lib,
+                err
+            );
+        }
+    }
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    // Polygons by material name, in order of first use.
+    let mut by_material: Vec<(Option<&Arc<obj::Material>>, Vec<&obj::SimplePolygon>)> = Vec::new();
+    for group in ob.data.objects.iter().flat_map(|o| &o.groups) {
+        let mtl = match &group.material {
+            Some(obj::ObjMaterial::Mtl(mtl)) => Some(mtl),
+            _ => None,
+        };
+        let name = |m: Option<&Arc<obj::Material>>| m.map(|m| m.name.clone());
+        match by_material.iter_mut().find(|(m, _)| name(*m) == name(mtl)) {
+            Some((_, polys)) => polys.extend(&group.polys),
+            None => by_material.push((mtl, group.polys.iter().collect())),
+        }
+    }
+
+    let mut meshes: Vec<HittablePtr> = Vec::new();
+    for (mtl, polys) in by_material {
+        let material = match mtl {
+            Some(mtl) => from_mtl(mtl, dir)?,
+            None => default_material.clone(),
+        };
+        if let Some(mesh) = TriangleMesh::new(MeshData::from_obj(&ob.data, polys), material) {
+            meshes.push(Arc::new(mesh));
+        }
+    }
+    BvhNode::new(&meshes, 0.0, 1.0).context("No triangles")
+}
+
+/// Index of `src[i]` in `out`, copying it there on first use.
+fn compact<T: Copy>(map: &mut HashMap<usize, u32>, out: &mut Vec<T>, src: &[T], i: usize) -> u32 {
+    *map.entry(i).or_insert_with(|| {
+        out.push(src[i]);
+        (out.len() - 1) as u32
+    })
+}
+
+/// Splits a simple polygon into triangles by ear clipping, keeping its
+/// winding. Returns indices into `points`.
+pub(super) fn triangulate(points: &[&Point3]) -> Vec<[usize; 3]> {
+    let n = points.len();
+    if n < 3 {
+        return Vec::new();
+    }
+
+    // Newell's method gives a robust normal even for concave polygons.
+    let mut normal = Vec3::default();
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % n];
+        normal += Vec3::new(
+            (a.y - b.y) * (a.z + b.z),
+            (a.z - b.z) * (a.x + b.x),
+            (a.x - b.x) * (a.y + b.y),
+        );
+    }
+    let left_of = |p: &Point3, a: &Point3, b: &Point3| (b - a).cross(&(p - a)).dot(&normal) >= 0.0;
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut triangles = Vec::with_capacity(n - 2);
+    while remaining.len() > 3 {
+        let m = remaining.len();
+        let corner = |k: usize| {
+            [
+                remaining[(k + m - 1) % m],
+                remaining[k],
+                remaining[(k + 1) % m],
+            ]
+        };
+        let ear = (0..m).find(|&k| {
+            let [a, b, c] = corner(k).map(|i| points[i]);
+            let convex = (b - a).cross(&(c - b)).dot(&normal) > 0.0;
+            convex
+                && remaining.iter().all(|&j| {
+                    corner(k).contains(&j) || {
+                        let p = points[j];
+                        !(left_of(p, a, b) && left_of(p, b, c) && left_of(p, c, a))
+                    }
+                })
+        });
+        match ear {
+            Some(k) => {
+                triangles.push(corner(k));
+                remaining.remove(k);
+            }
+            // Degenerate or self-intersecting; fan what is left.
+            None => break,
+        }
+    }
+    for k in 1..remaining.len() - 1 {
+        triangles.push([remaining[0], remaining[k], remaining[k + 1]]);
+    }
+    triangles
+}
+
+fn reorder(indices: &[[u32; 3]], order: &[usize]) -> Vec<[u32; 3]> {
+    order.iter().map(|&i| indices[i]).collect()
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64, _: &mut Random) -> Option<HitRecord> {
+        self.bvh.hit(r, t_min, t_max, |i, t_max| {
+            self.hit_triangle(i, r, t_min, t_max)
+        })
+    }
+
+    fn bounding_box(&self, _: f64, _: f64) -> Option<Aabb> {
+        Some(self.bvh.bounding_box().clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::Color;
+
+    #[test]
+    fn interpolates_uvs_and_normals() {
+        let data = MeshData {
+            positions: vec![
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+            ],
+            colors: Vec::new(),
+            normals: vec![Vec3::new(0.0, 0.0, 1.0), Vec3::new(1.0, 0.0, 1.0)],
+            uvs: vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]],
+            indices: vec![[0, 1, 2]],
+            normal_indices: Some(vec![[0, 1, 0]]),
+            uv_indices: Some(vec![[0, 1, 2]]),
+        };
+        let mat = Arc::new(Lambertian::with_color(Color::new(0.5, 0.5, 0.5)));
+        let mesh = TriangleMesh::new(data, mat).unwrap();
+
+        let r = Ray::new(Point3::new(0.25, 0.5, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let rec = mesh
+            .hit(&r, 0.001, f64::INFINITY, &mut Random::new(0))
+            .unwrap();
+        assert!((rec.t - 1.0).abs() < 1e-9);
+        assert!((rec.u - 0.25).abs() < 1e-9 && (rec.v - 0.5).abs() < 1e-9);
+        let expected = Vec3::new(0.25, 0.0, 1.0).unit_vector();
+        assert!((&rec.normal - expected).length() < 1e-9);
+    }
+
+    #[test]
+    fn angle_weighted_normals() {
+        // A cube corner at the origin, with the z = 0 face split in two.
+        let mut data = MeshData {
+            positions: vec![
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+                Point3::new(0.0, 0.0, 1.0),
+                Point3::new(1.0, 1.0, 0.0),
+            ],
+            indices: vec![[0, 3, 2], [0, 1, 3], [0, 4, 1], [0, 2, 4]],
+            ..Default::default()
+        };
+        data.generate_normals();
+        let expected = Vec3::new(-1.0, -1.0, -1.0).unit_vector();
+        assert!((&data.normals[0] - expected).length() < 1e-9);
+        assert_eq!(data.normal_indices, Some(data.indices.clone()));
+    }
+
+    #[test]
+    fn loads_all_groups_and_ngons() {
+        let source = "\
+v 0 0 0
+v 2 0 0
+v 2 2 0
+v 1 1 0
+v 0 2 0
+v 0 0 -1
+v 1 0 -1
+v 0 1 -1
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0.5 0.5
+vt 0 1
+o concave
+f 3/3 4/4 5/5 1/1 2/2
+o other
+g triangle
+f 6/1 7/2 8/5
+";
+        let ob = obj::ObjData::load_buf(source.as_bytes()).unwrap();
+        let mat = Arc::new(Lambertian::with_color(Color::new(0.5, 0.5, 0.5)));
+        let mesh = TriangleMesh::from_obj(&ob, mat).unwrap();
+        assert_eq!(mesh.len(), 4);
+
+        let mut rng = Random::new(0);
+        let down = Vec3::new(0.0, 0.0, -1.0);
+        let hit = |x: f64, y: f64, rng: &mut Random| {
+            let r = Ray::new(Point3::new(x, y, 1.0), down.clone(), 0.0);
+            mesh.hit(&r, 0.001, f64::INFINITY, rng)
+        };
+        // Inside the notch of the arrow-shaped pentagon.
+        assert!(hit(1.0, 1.5, &mut rng).is_none());
+        let rec = hit(1.5, 0.5, &mut rng).unwrap();
+        assert!((rec.t - 1.0).abs() < 1e-9);
+        assert!((rec.u - 0.75).abs() < 1e-9 && (rec.v - 0.25).abs() < 1e-9);
+        let rec = hit(0.25, 0.25, &mut rng).unwrap();
+        assert!((rec.t - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn flat_shaded_stl() {
+        let source = "\
+solid square
+facet normal 0 0 1
+  outer loop
+    vertex 0 0 0
+    vertex 1 0 0
+    vertex 1 1 0
+  endloop
+endfacet
+facet normal 0 0 1
+  outer loop
+    vertex 0 0 0
+    vertex 1 1 0
+    vertex 0 1 0
+  endloop
+endfacet
+endsolid square
+";
+        let data = MeshData::from_stl(&mut std::io::Cursor::new(source)).unwrap();
+        assert_eq!(data.positions.len(), 4);
+        assert_eq!(data.indices.len(), 2);
+        assert!(data.normal_indices.is_none());
+    }
+
+    #[test]
+    fn materials_from_mtl() {
+        let dir = std::env::temp_dir().join(format!(
+            "raytracing-{}-materials-from-mtl",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("lamp.mtl"),
+            "newmtl glow\nKe 4 4 4\nnewmtl matte\nKd 0.2 0.4 0.6\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("lamp.obj"),
+            "mtllib lamp.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\n\
+             usemtl glow\nf 1 2 3\nusemtl matte\nf 2 4 3\n",
+        )
+        .unwrap();
+
+        let default = Arc::new(Lambertian::with_color(Color::new(0.5, 0.5, 0.5)));
+        let model = load_obj(dir.join("lamp.obj"), default).unwrap();
+        let mut rng = Random::new(0);
+        let emitted = |x: f64, y: f64, rng: &mut Random| {
+            let r = Ray::new(Point3::new(x, y, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+            let rec = model.hit(&r, 0.001, f64::INFINITY, rng).unwrap();
+            rec.mat_ptr.emmitted(rec.u, rec.v, &rec.p)
+        };
+        assert_eq!(emitted(0.2, 0.2, &mut rng), Color::new(4.0, 4.0, 4.0));
+        assert_eq!(emitted(0.8, 0.8, &mut rng), Color::default());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_mtl_falls_back_to_default() {
+        let dir =
+            std::env::temp_dir().join(format!("raytracing-{}-missing-mtl", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("lamp.obj"),
+            "mtllib gone.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl glow\nf 1 2 3\n",
+        )
+        .unwrap();
+
+        let default: MaterialPtr = Arc::new(Lambertian::with_color(Color::new(0.5, 0.5, 0.5)));
+        let model = load_obj(dir.join("lamp.obj"), default.clone()).unwrap();
+        let mut rng = Random::new(0);
+        let r = Ray::new(Point3::new(0.2, 0.2, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let rec = model.hit(&r, 0.001, f64::INFINITY, &mut rng).unwrap();
+        assert!(Arc::ptr_eq(&rec.mat_ptr, &default));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
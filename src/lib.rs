@@ -7,19 +7,25 @@ pub mod camera;
 pub mod color;
 pub mod hittable;
 pub mod material;
+pub mod onb;
 pub mod opt;
+pub mod output;
+pub mod pdf;
 pub mod random;
 pub mod ray;
+pub mod renderer;
 pub mod scene;
 pub mod texture;
+pub mod tone_map;
 pub mod vec3;
 
 pub use camera::Camera;
 pub use color::Color;
 pub use hittable::{HitRecord, Hittable, HittablePtr};
-pub use material::{Material, MaterialPtr};
+pub use material::{Material, MaterialPtr, ScatterRecord};
 pub use opt::Opt;
 pub use random::Random;
 pub use ray::Ray;
+pub use renderer::{RenderSettings, Renderer};
 pub use texture::{Texture, TexturePtr};
 pub use vec3::{Point3, Vec3};
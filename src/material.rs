@@ -1,9 +1,22 @@
 use std::sync::Arc;
 
+use crate::pdf::PdfPtr;
 use crate::{Color, HitRecord, Point3, Random, Ray};
 
+pub enum ScatterRecord {
+    /// A single outgoing ray whose radiance is scaled by `attenuation`.
+    Specular { attenuation: Color, ray: Ray },
+    /// Directions are drawn from the PDF and weighted by `Material::eval`.
+    Pdf(PdfPtr),
+}
+
 pub trait Material {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut Random) -> Option<(Color, Ray)>;
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut Random) -> Option<ScatterRecord>;
+    /// BSDF times the cosine term for light leaving along `scattered`.
+    /// Only called for `ScatterRecord::Pdf`.
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray) -> Color {
+        Color::default()
+    }
     fn emmitted(&self, _u: f64, _v: f64, _p: &Point3) -> Color {
         Color::default()
     }
@@ -11,14 +24,22 @@ pub trait Material {
 
 pub type MaterialPtr = Arc<dyn Material + Send + Sync>;
 
+pub mod conductor;
 pub mod dielectric;
 pub mod diffuse_light;
 pub mod isotropic;
 pub mod lambertian;
 pub mod metal;
+pub mod mtl;
+pub mod principled;
+pub mod rough_dielectric;
 
+pub use conductor::{Conductor, Fresnel};
 pub use dielectric::Dielectric;
 pub use diffuse_light::DiffuseLight;
 pub use isotropic::Isotropic;
 pub use lambertian::Lambertian;
 pub use metal::Metal;
+pub use mtl::from_mtl;
+pub use principled::Principled;
+pub use rough_dielectric::RoughDielectric;
@@ -0,0 +1,209 @@
+use crate::pdf::{Ggx, GgxPdf};
+use crate::{Color, HitRecord, Material, Random, Ray, ScatterRecord};
+
+/// Reflectance of a metal at a given angle of incidence.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fresnel {
+    /// Schlick's approximation from the reflectance at normal incidence.
+    Schlick(Color),
+    /// Exact Fresnel equations for a complex index of refraction
+    /// `eta + i k` per color channel, against air.
+    Complex { eta: Color, k: Color },
+}
+
+impl Fresnel {
+    pub fn eval(&self, cos_theta: f64) -> Color {
+        let cos = cos_theta.clamp(0.0, 1.0);
+        match self {
+            Fresnel::Schlick(f0) => f0.map(|f| f + (1.0 - f) * (1.0 - cos).powi(5)),
+            Fresnel::Complex { eta, k } => Color::new(
+                fresnel_complex(cos, eta.0.x, k.0.x),
+                fresnel_complex(cos, eta.0.y, k.0.y),
+                fresnel_complex(cos, eta.0.z, k.0.z),
+            ),
+        }
+    }
+}
+
+/// Unpolarized reflectance of a conductor (pbrt's `FrConductor`).
+fn fresnel_complex(cos: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = cos * cos;
+    let sin2 = 1.0 - cos2;
+    let t0 = eta * eta - k * k - sin2;
+    let a2b2 = (t0 * t0 + 4.0 * eta * eta * k * k).sqrt();
+    let t1 = a2b2 + cos2;
+    let a = (0.5 * (a2b2 + t0)).max(0.0).sqrt();
+    let t2 = 2.0 * cos * a;
+    let rs = (t1 - t2) / (t1 + t2);
+    let t3 = cos2 * a2b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+    0.5 * (rp + rs)
+}
+
+/// Rough metal with a GGX microfacet distribution (Cook-Torrance with
+/// height-correlated Smith masking), sampled by visible normals.
+#[derive(Debug, Clone)]
+pub struct Conductor {
+    fresnel: Fresnel,
+    ggx: Ggx,
+}
+
+impl Conductor {
+    /// `roughness` is perceptual, from 0 (mirror) to 1.
+    pub fn new(fresnel: Fresnel, roughness: f64) -> Self {
+        Self {
+            fresnel,
+            ggx: Ggx::new(roughness),
+        }
+    }
+
+    /// Schlick Fresnel with reflectance `f0` at normal incidence.
+    pub fn schlick(f0: Color, roughness: f64) -> Self {
+        Self::new(Fresnel::Schlick(f0), roughness)
+    }
+
+    /// Complex index of refraction per channel at 650, 550 and 450 nm.
+    pub fn complex(eta: Color, k: Color, roughness: f64) -> Self {
+        Self::new(Fresnel::Complex { eta, k }, roughness)
+    }
+
+    pub fn gold(roughness: f64) -> Self {
+        Self::complex(
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.385, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        Self::complex(
+            Color::new(0.200, 0.924, 1.102),
+            Color::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn aluminium(roughness: f64) -> Self {
+        Self::complex(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: f64) -> Self {
+        Self::complex(
+            Color::new(0.155, 0.117, 0.138),
+            Color::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, _: &mut Random) -> Option<ScatterRecord> {
+        let wo = -r_in.dir.unit_vector();
+        let cos_o = wo.dot(&rec.normal);
+        if cos_o <= 0.0 {
+            return None;
+        }
+        if self.ggx.is_smooth() {
+            return Some(ScatterRecord::Specular {
+                attenuation: self.fresnel.eval(cos_o),
+                ray: Ray::new(rec.p.clone(), (-wo).reflect(&rec.normal), r_in.time),
+            });
+        }
+        Some(ScatterRecord::Pdf(Box::new(GgxPdf::new(
+            &wo,
+            &rec.normal,
+            self.ggx,
+        ))))
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        let wo = -r_in.dir.unit_vector();
+        let wi = scattered.dir.unit_vector();
+        let h = (&wo + &wi).unit_vector();
+        self.fresnel.eval(wi.dot(&h)) * self.ggx.reflection(&wo, &wi, &rec.normal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::{Pdf, UniformSpherePdf};
+    use crate::{Point3, Vec3};
+    use std::sync::Arc;
+
+    fn record(material: Conductor) -> (Ray, HitRecord) {
+        let r_in = Ray::new(Point3::new(-1.0, 1.0, 0.3), Vec3::new(1.0, -1.0, -0.3), 0.0);
+        let rec = HitRecord::new(
+            Point3::default(),
+            1.0,
+            0.0,
+            0.0,
+            &r_in,
+            Vec3::new(0.0, 1.0, 0.0),
+            Arc::new(material),
+        );
+        (r_in, rec)
+    }
+
+    #[test]
+    fn visible_normal_sampling_matches_eval() {
+        let mut rng = Random::new(5);
+        let white = Color::new(1.0, 1.0, 1.0);
+        // Directional albedo by numerical quadrature. Single scattering
+        // loses energy to masking, the more so the rougher the surface.
+        for (roughness, expected) in [(0.5, 0.884), (0.8, 0.583), (1.0, 0.381)] {
+            let (r_in, rec) = record(Conductor::schlick(white.clone(), roughness));
+            let pdf = match rec.mat_ptr.scatter(&r_in, &rec, &mut rng) {
+                Some(ScatterRecord::Pdf(pdf)) => pdf,
+                _ => panic!("expected a pdf"),
+            };
+            let n = 100_000;
+            let albedo = |dir: Vec3, p: f64| {
+                if p <= 0.0 {
+                    return 0.0;
+                }
+                let r = Ray::new(rec.p.clone(), dir, 0.0);
+                rec.mat_ptr.eval(&r_in, &rec, &r).0.x / p
+            };
+            let (mut sampled, mut above, mut integral) = (0.0, 0.0, 0.0);
+            for _ in 0..n {
+                let dir = pdf.generate(&mut rng);
+                let p = pdf.value(&dir, &mut rng);
+                if p > 0.0 {
+                    above += 1.0;
+                }
+                sampled += albedo(dir, p);
+                let dir = UniformSpherePdf.generate(&mut rng);
+                let q = UniformSpherePdf.value(&dir, &mut rng);
+                integral += pdf.value(&dir, &mut rng) / q;
+            }
+            let n = n as f64;
+            assert!((sampled / n - expected).abs() < 0.01, "{}", sampled / n);
+            // Samples reflected below the surface are the missing mass.
+            assert!(
+                (integral / n - above / n).abs() < 0.03,
+                "{} {}",
+                integral,
+                above
+            );
+        }
+    }
+
+    #[test]
+    fn complex_fresnel() {
+        let gold = Conductor::gold(0.0).fresnel;
+        let f = gold.eval(1.0);
+        // Gold reflects red more than blue, and everything at grazing.
+        assert!(f.0.x > 0.9 && f.0.z < 0.45, "{:?}", f);
+        let grazing = gold.eval(1e-6);
+        assert!(grazing.0.z > 0.99, "{:?}", grazing);
+        // Without absorption it is the dielectric reflectance.
+        let r0 = ((1.5f64 - 1.0) / (1.5 + 1.0)).powi(2);
+        assert!((fresnel_complex(1.0, 1.5, 0.0) - r0).abs() < 1e-12);
+    }
+}
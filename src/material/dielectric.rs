@@ -1,22 +1,59 @@
-use crate::{Color, HitRecord, Material, Random, Ray};
+use crate::{Color, HitRecord, Material, Random, Ray, ScatterRecord};
 
 #[derive(Debug, PartialOrd, PartialEq, Clone, Default)]
 pub struct Dielectric {
     ir: f64,
+    /// Absorption coefficient per unit length inside, for each channel.
+    absorption: Color,
 }
 
 impl Dielectric {
     pub fn new(ir: f64) -> Self {
-        Self { ir }
+        Self::with_absorption(ir, Color::default())
     }
+
+    /// Absorbing glass or liquid: light is attenuated by Beer-Lambert's law
+    /// over the distance it travels between entering and leaving. Objects
+    /// inside the medium do not attenuate their part of the path.
+    pub fn with_absorption(ir: f64, absorption: Color) -> Self {
+        Self { ir, absorption }
+    }
+
+    /// Absorbing medium that turns white light into `color` after it has
+    /// traveled `distance`.
+    pub fn tinted(ir: f64, color: Color, distance: f64) -> Self {
+        Self::with_absorption(ir, Self::absorption_for(color, distance))
+    }
+
+    /// Absorption coefficient that turns white light into `color` after it
+    /// has traveled `distance`.
+    pub fn absorption_for(color: Color, distance: f64) -> Color {
+        color.map(|c| -c.ln() / distance)
+    }
+
     fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
         let r0 = ((1.0 - ref_idx) / (1.0 + ref_idx)).powi(2);
         r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
     }
 }
 
+/// Unpolarized Fresnel reflectance at an interface where `eta` is the index
+/// of refraction of the far side over that of the near side, for light
+/// arriving at `cos_i` to the normal. It is 1 under total internal reflection.
+pub(super) fn fresnel(cos_i: f64, eta: f64) -> f64 {
+    let cos_i = cos_i.clamp(0.0, 1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (parallel * parallel + perpendicular * perpendicular) / 2.0
+}
+
 impl Material for Dielectric {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut Random) -> Option<(Color, Ray)> {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut Random) -> Option<ScatterRecord> {
         let refraction_ratio = if rec.front_face {
             1.0 / self.ir
         } else {
@@ -35,9 +72,51 @@ impl Material for Dielectric {
             unit_direction.refract(&rec.normal, refraction_ratio)
         };
 
-        Some((
-            Color::new(1.0, 1.0, 1.0),
-            Ray::new(rec.p.clone(), direction, r_in.time),
-        ))
+        // A back face hit ends a path segment through the medium.
+        let attenuation = if rec.front_face {
+            Color::new(1.0, 1.0, 1.0)
+        } else {
+            let distance = rec.t * r_in.dir.length();
+            self.absorption.map(|a| (-a * distance).exp())
+        };
+
+        Some(ScatterRecord::Specular {
+            attenuation,
+            ray: Ray::new(rec.p.clone(), direction, r_in.time),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::hittable::{Hittable, Sphere};
+    use crate::{Point3, Vec3};
+
+    #[test]
+    fn absorbs_along_the_path_inside() {
+        let glass = Dielectric::tinted(1.5, Color::new(0.5, 0.25, 1.0), 2.0);
+        let sphere = Sphere::new(Point3::default(), 1.0, Arc::new(glass));
+        let mut rng = Random::new(0);
+        let attenuation = |r: &Ray, rng: &mut Random| {
+            let rec = sphere.hit(r, 0.001, f64::INFINITY, rng).unwrap();
+            match rec.mat_ptr.scatter(r, &rec, rng) {
+                Some(ScatterRecord::Specular { attenuation, .. }) => attenuation,
+                _ => panic!("expected a specular bounce"),
+            }
+        };
+
+        // Entering is free.
+        let outside = Ray::new(Point3::new(0.0, 0.0, -3.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        assert_eq!(attenuation(&outside, &mut rng), Color::new(1.0, 1.0, 1.0));
+
+        // Half the tint distance from the center out, with a ray direction
+        // that is not a unit vector.
+        let inside = Ray::new(Point3::default(), Vec3::new(0.0, 0.0, 2.0), 0.0);
+        let expected = Color::new(0.5f64.sqrt(), 0.5, 1.0);
+        let a = attenuation(&inside, &mut rng);
+        assert!((&a.0 - &expected.0).length() < 1e-12, "{:?}", a);
     }
 }
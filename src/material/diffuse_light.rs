@@ -3,7 +3,7 @@ use std::sync::Arc;
 use super::Material;
 
 use crate::texture::SolidColor;
-use crate::{Color, HitRecord, Point3, Random, Ray, TexturePtr};
+use crate::{Color, HitRecord, Point3, Random, Ray, ScatterRecord, TexturePtr};
 
 #[derive(Clone)]
 pub struct DiffuseLight {
@@ -21,7 +21,7 @@ impl DiffuseLight {
 }
 
 impl Material for DiffuseLight {
-    fn scatter(&self, _: &Ray, _: &HitRecord, _: &mut Random) -> Option<(Color, Ray)> {
+    fn scatter(&self, _: &Ray, _: &HitRecord, _: &mut Random) -> Option<ScatterRecord> {
         None
     }
 
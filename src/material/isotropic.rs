@@ -1,4 +1,7 @@
-use crate::{Color, HitRecord, Material, Random, Ray, TexturePtr, Vec3};
+use std::f64::consts::PI;
+
+use crate::pdf::UniformSpherePdf;
+use crate::{Color, HitRecord, Material, Random, Ray, ScatterRecord, TexturePtr};
 
 #[derive(Clone)]
 pub struct Isotropic {
@@ -14,10 +17,11 @@ impl Isotropic {
 }
 
 impl Material for Isotropic {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut Random) -> Option<(Color, Ray)> {
-        Some((
-            self.albedo.value(rec.u, rec.v, &rec.p),
-            Ray::new(rec.p.clone(), Vec3::random_in_unit_sphere(rng), r_in.time),
-        ))
+    fn scatter(&self, _: &Ray, _: &HitRecord, _: &mut Random) -> Option<ScatterRecord> {
+        Some(ScatterRecord::Pdf(Box::new(UniformSpherePdf)))
+    }
+
+    fn eval(&self, _: &Ray, rec: &HitRecord, _: &Ray) -> Color {
+        self.albedo.value(rec.u, rec.v, &rec.p) / (4.0 * PI)
     }
 }
@@ -1,7 +1,9 @@
+use std::f64::consts::PI;
 use std::sync::Arc;
 
+use crate::pdf::CosinePdf;
 use crate::texture::SolidColor;
-use crate::{Color, HitRecord, Material, Random, Ray, TexturePtr, Vec3};
+use crate::{Color, HitRecord, Material, Random, Ray, ScatterRecord, TexturePtr};
 
 #[derive(Clone)]
 pub struct Lambertian {
@@ -18,16 +20,12 @@ impl Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut Random) -> Option<(Color, Ray)> {
-        let scatter_direction = &rec.normal + Vec3::random_unit_vector(rng);
-        let scatter_direction = if scatter_direction.near_zero() {
-            rec.normal.clone()
-        } else {
-            scatter_direction
-        };
-        Some((
-            self.albedo.value(rec.u, rec.v, &rec.p),
-            Ray::new(rec.p.clone(), scatter_direction, r_in.time),
-        ))
+    fn scatter(&self, _: &Ray, rec: &HitRecord, _: &mut Random) -> Option<ScatterRecord> {
+        Some(ScatterRecord::Pdf(Box::new(CosinePdf::new(&rec.normal))))
+    }
+
+    fn eval(&self, _: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        let cosine = rec.normal.dot(&scattered.dir.unit_vector()).max(0.0);
+        self.albedo.value(rec.u, rec.v, &rec.p) * (cosine / PI)
     }
 }
@@ -1,4 +1,5 @@
-use crate::{Color, HitRecord, Material, Random, Ray, Vec3};
+use crate::pdf::FuzzPdf;
+use crate::{Color, HitRecord, Material, Random, Ray, ScatterRecord};
 
 #[derive(Debug, PartialOrd, PartialEq, Clone, Default)]
 pub struct Metal {
@@ -14,17 +15,29 @@ impl Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut Random) -> Option<(Color, Ray)> {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, _: &mut Random) -> Option<ScatterRecord> {
         let reflected = r_in.dir.unit_vector().reflect(&rec.normal);
-        let scattered = Ray::new(
-            rec.p.clone(),
-            reflected + self.fuzz * Vec3::random_in_unit_sphere(rng),
-            r_in.time,
-        );
-        if scattered.dir.dot(&rec.normal) > 0.0 {
-            Some((self.albedo.clone(), scattered))
+        if self.fuzz > 0.0 {
+            return Some(ScatterRecord::Pdf(Box::new(FuzzPdf::new(
+                &reflected, self.fuzz,
+            ))));
+        }
+        if reflected.dot(&rec.normal) > 0.0 {
+            Some(ScatterRecord::Specular {
+                attenuation: self.albedo.clone(),
+                ray: Ray::new(rec.p.clone(), reflected, r_in.time),
+            })
         } else {
             None
         }
     }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        // Directions below the surface are absorbed.
+        if scattered.dir.dot(&rec.normal) <= 0.0 {
+            return Color::default();
+        }
+        let reflected = r_in.dir.unit_vector().reflect(&rec.normal);
+        &self.albedo * FuzzPdf::new(&reflected, self.fuzz).density(&scattered.dir)
+    }
 }
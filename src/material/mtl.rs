@@ -0,0 +1,76 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+
+use super::{DiffuseLight, MaterialPtr, Principled};
+use crate::texture::{ImageTexture, SolidColor};
+use crate::{Color, TexturePtr};
+
+/// Maps a Wavefront MTL material onto this crate: emissive (`Ke`) to
+/// `DiffuseLight` and anything else to `Principled` with a roughness derived
+/// from `Ns`. Transparent materials (`d < 1` or an `illum` with refraction)
+/// transmit through an index of refraction of `Ni`, tinted by `Tf`;
+/// specular-dominated ones are metals colored by `Ks`; the rest take `Kd` or
+/// `map_Kd` as base color with `Ks` as specular reflectance. Texture paths
+/// are relative to `dir`.
+pub fn from_mtl(mtl: &obj::Material, dir: &Path) -> anyhow::Result<MaterialPtr> {
+    let color = |c: Option<[f32; 3]>| {
+        c.map_or(Color::default(), |[r, g, b]| {
+            Color::new(r as f64, g as f64, b as f64)
+        })
+    };
+    let texture = |map: &Option<String>, c: Color| -> anyhow::Result<TexturePtr> {
+        Ok(match map {
+            Some(file) => Arc::new(
+                ImageTexture::new(dir.join(file))
+                    .with_context(|| format!("material `{}`", mtl.name))?,
+            ),
+            None => Arc::new(SolidColor::new(c)),
+        })
+    };
+
+    let ke = color(mtl.ke);
+    if ke.luminance() > 0.0 || mtl.map_ke.is_some() {
+        return Ok(Arc::new(DiffuseLight::new(texture(&mtl.map_ke, ke)?)));
+    }
+
+    // Phong exponent to GGX roughness, with alpha = sqrt(2 / (Ns + 2)).
+    let ns = mtl.ns.unwrap_or(0.0) as f64;
+    let roughness = Principled::constant((2.0 / (ns + 2.0)).sqrt().sqrt().min(1.0));
+
+    let dissolve = mtl.d.or(mtl.tr.map(|tr| 1.0 - tr)).unwrap_or(1.0) as f64;
+    if dissolve < 1.0 || matches!(mtl.illum, Some(4 | 6 | 7 | 9)) {
+        let transmission = if dissolve < 1.0 { 1.0 - dissolve } else { 1.0 };
+        return Ok(Arc::new(Principled {
+            base_color: match mtl.tf {
+                Some(_) => color(mtl.tf),
+                None => Color::new(1.0, 1.0, 1.0),
+            }
+            .into(),
+            roughness,
+            transmission: Principled::constant(transmission),
+            ir: mtl.ni.unwrap_or(1.5) as f64,
+            ..Default::default()
+        }));
+    }
+
+    let kd = color(mtl.kd);
+    let ks = color(mtl.ks);
+    if mtl.map_kd.is_none() && (ks.luminance() > kd.luminance() || mtl.illum == Some(3)) {
+        return Ok(Arc::new(Principled {
+            base_color: ks.into(),
+            metallic: Principled::constant(1.0),
+            roughness,
+            ..Default::default()
+        }));
+    }
+
+    Ok(Arc::new(Principled {
+        base_color: texture(&mtl.map_kd, kd)?,
+        roughness,
+        // `specular` 1 reflects 8% at normal incidence.
+        specular: Principled::constant((ks.luminance() / 0.08).min(1.0)),
+        ..Default::default()
+    }))
+}
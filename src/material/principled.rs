@@ -0,0 +1,339 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use super::rough_dielectric::Lobes;
+use super::Fresnel;
+use crate::pdf::{CosinePdf, Ggx, GgxPdf, Pdf, PdfPtr};
+use crate::texture::SolidColor;
+use crate::{Color, HitRecord, Material, Random, Ray, ScatterRecord, TexturePtr, Vec3};
+
+/// Reflectance at normal incidence of the clear coat, a polyurethane-like
+/// layer with an index of refraction of 1.5.
+const CLEARCOAT_F0: f64 = 0.04;
+
+/// One material for plastics, metals, glass and coated surfaces, after
+/// Disney's principled BSDF. Every parameter but `ir` and `absorption` is a
+/// texture; the scalar ones read its luminance and go from 0 to 1.
+///
+/// A GGX clear coat sits over a mix of a conductor (`metallic`), a rough
+/// dielectric that transmits light tinted by `base_color` (`transmission`)
+/// and, for what remains, a diffuse base with a GGX specular layer and a
+/// white sheen at grazing angles.
+#[derive(Clone)]
+pub struct Principled {
+    /// Diffuse albedo, reflectance of metals and tint of transmission.
+    pub base_color: TexturePtr,
+    pub metallic: TexturePtr,
+    /// Perceptual roughness of the specular, metal and glass lobes.
+    pub roughness: TexturePtr,
+    /// Specular reflectance of non-metals; 0.5 is that of glass.
+    pub specular: TexturePtr,
+    pub clearcoat: TexturePtr,
+    pub clearcoat_roughness: TexturePtr,
+    pub sheen: TexturePtr,
+    pub transmission: TexturePtr,
+    /// Index of refraction for transmission.
+    pub ir: f64,
+    /// Absorption per unit length inside, as with `Dielectric`.
+    pub absorption: Color,
+}
+
+impl Default for Principled {
+    fn default() -> Self {
+        Self {
+            base_color: Color::new(0.8, 0.8, 0.8).into(),
+            metallic: Self::constant(0.0),
+            roughness: Self::constant(0.5),
+            specular: Self::constant(0.5),
+            clearcoat: Self::constant(0.0),
+            clearcoat_roughness: Self::constant(0.03),
+            sheen: Self::constant(0.0),
+            transmission: Self::constant(0.0),
+            ir: 1.5,
+            absorption: Color::default(),
+        }
+    }
+}
+
+impl Principled {
+    /// Texture for a scalar parameter that does not vary.
+    pub fn constant(x: f64) -> TexturePtr {
+        Arc::new(SolidColor::new(Color::new(x, x, x)))
+    }
+
+    fn params(&self, r_in: &Ray, rec: &HitRecord) -> Params {
+        let scalar = |t: &TexturePtr| t.value(rec.u, rec.v, &rec.p).luminance().clamp(0.0, 1.0);
+        let base_color = self.base_color.value(rec.u, rec.v, &rec.p);
+        let (metallic, transmission) = (scalar(&self.metallic), scalar(&self.transmission));
+        let wo = -r_in.dir.unit_vector();
+        let cos_o = wo.dot(&rec.normal);
+        let clearcoat = scalar(&self.clearcoat);
+        Params {
+            under_coat: 1.0 - clearcoat * schlick(CLEARCOAT_F0, cos_o),
+            wo,
+            cos_o,
+            base_color,
+            metallic,
+            opaque: (1.0 - metallic) * (1.0 - transmission),
+            transmitted: (1.0 - metallic) * transmission,
+            ggx: Ggx::new(scalar(&self.roughness)),
+            f0: 0.08 * scalar(&self.specular),
+            clearcoat,
+            coat: Ggx::new(scalar(&self.clearcoat_roughness)),
+            sheen: scalar(&self.sheen),
+        }
+    }
+
+    fn glass(&self, params: &Params, rec: &HitRecord) -> Lobes {
+        let eta = if rec.front_face {
+            self.ir
+        } else {
+            1.0 / self.ir
+        };
+        Lobes::new(&params.wo, &rec.normal, params.ggx, eta)
+    }
+}
+
+/// Parameters at a hit point, with the lobe weights derived from them.
+struct Params {
+    wo: Vec3,
+    cos_o: f64,
+    base_color: Color,
+    metallic: f64,
+    /// Weight of the diffuse base and its specular layer.
+    opaque: f64,
+    /// Weight of the rough dielectric.
+    transmitted: f64,
+    ggx: Ggx,
+    /// Reflectance at normal incidence of the specular layer.
+    f0: f64,
+    clearcoat: f64,
+    coat: Ggx,
+    sheen: f64,
+    /// Light not reflected by the clear coat.
+    under_coat: f64,
+}
+
+impl Params {
+    /// Reflectance of the metal and specular layers together.
+    fn specular(&self, cos: f64) -> Color {
+        let white = Color::new(1.0, 1.0, 1.0);
+        Fresnel::Schlick(self.base_color.clone()).eval(cos) * self.metallic
+            + white * (self.opaque * schlick(self.f0, cos))
+    }
+}
+
+fn schlick(f0: f64, cos: f64) -> f64 {
+    f0 + (1.0 - f0) * (1.0 - cos.clamp(0.0, 1.0)).powi(5)
+}
+
+impl Material for Principled {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, _: &mut Random) -> Option<ScatterRecord> {
+        let p = self.params(r_in, rec);
+        if p.cos_o <= 0.0 {
+            return None;
+        }
+
+        // Pick lobes in proportion to the light each reflects towards the
+        // viewer, roughly.
+        let n = &rec.normal;
+        let mut lobes: Vec<(f64, PdfPtr)> = Vec::new();
+        let coat = p.clearcoat * schlick(CLEARCOAT_F0, p.cos_o);
+        if coat > 0.0 {
+            lobes.push((coat, Box::new(GgxPdf::new(&p.wo, n, p.coat))));
+        }
+        let specular = p.under_coat * p.specular(p.cos_o).luminance();
+        if specular > 0.0 {
+            lobes.push((specular, Box::new(GgxPdf::new(&p.wo, n, p.ggx))));
+        }
+        let diffuse = p.under_coat * p.opaque * (p.base_color.luminance() + p.sheen);
+        if diffuse > 0.0 {
+            lobes.push((diffuse, Box::new(CosinePdf::new(n))));
+        }
+        if p.transmitted > 0.0 {
+            lobes.push((p.under_coat * p.transmitted, Box::new(self.glass(&p, rec))));
+        }
+        if lobes.is_empty() {
+            return None;
+        }
+        Some(ScatterRecord::Pdf(Box::new(LobePdf::new(lobes))))
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        let p = self.params(r_in, rec);
+        let wi = scattered.dir.unit_vector();
+        let n = &rec.normal;
+        let cos_i = wi.dot(n);
+        if p.cos_o <= 0.0 {
+            return Color::default();
+        }
+
+        let mut f = Color::default();
+        if cos_i > 0.0 {
+            let cos_d = wi.dot(&(&p.wo + &wi).unit_vector());
+            let coat =
+                p.clearcoat * schlick(CLEARCOAT_F0, cos_d) * p.coat.reflection(&p.wo, &wi, n);
+            let specular = p.specular(cos_d) * p.ggx.reflection(&p.wo, &wi, n);
+            let diffuse = &p.base_color * ((1.0 - schlick(p.f0, p.cos_o)) / PI)
+                + Color::new(1.0, 1.0, 1.0) * (p.sheen * (1.0 - cos_d).powi(5));
+            f += Color::new(coat, coat, coat);
+            f += (specular + diffuse * (p.opaque * cos_i)) * p.under_coat;
+        }
+        if p.transmitted > 0.0 {
+            let glass = self.glass(&p, rec).eval(&wi) * p.transmitted * p.under_coat;
+            f += if cos_i < 0.0 {
+                &p.base_color * glass
+            } else {
+                Color::new(glass, glass, glass)
+            };
+        }
+
+        // A back face hit ends a path segment through the medium.
+        if !rec.front_face {
+            let distance = rec.t * r_in.dir.length();
+            f = f * self.absorption.map(|a| (-a * distance).exp());
+        }
+        f
+    }
+}
+
+/// Mixture of lobe densities with the given weights.
+struct LobePdf {
+    /// Normalized weights.
+    lobes: Vec<(f64, PdfPtr)>,
+}
+
+impl LobePdf {
+    fn new(mut lobes: Vec<(f64, PdfPtr)>) -> Self {
+        let total: f64 = lobes.iter().map(|(w, _)| w).sum();
+        for (w, _) in &mut lobes {
+            *w /= total;
+        }
+        Self { lobes }
+    }
+}
+
+impl Pdf for LobePdf {
+    fn value(&self, direction: &Vec3, rng: &mut Random) -> f64 {
+        // The glass lobe gives no direction as the zero vector.
+        if direction.near_zero() {
+            return 0.0;
+        }
+        self.lobes
+            .iter()
+            .map(|(w, pdf)| w * pdf.value(direction, rng))
+            .sum()
+    }
+
+    fn generate(&self, rng: &mut Random) -> Vec3 {
+        let mut x = rng.unit_f64();
+        for (w, pdf) in &self.lobes {
+            if x < *w {
+                return pdf.generate(rng);
+            }
+            x -= w;
+        }
+        self.lobes.last().unwrap().1.generate(rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::{Conductor, MaterialPtr};
+    use crate::pdf::UniformSpherePdf;
+    use crate::Point3;
+
+    fn record(material: MaterialPtr) -> (Ray, HitRecord) {
+        let r_in = Ray::new(Point3::new(-1.0, 1.0, 0.3), Vec3::new(1.0, -1.0, -0.3), 0.0);
+        let rec = HitRecord::new(
+            Point3::default(),
+            1.0,
+            0.0,
+            0.0,
+            &r_in,
+            Vec3::new(0.0, 1.0, 0.0),
+            material,
+        );
+        (r_in, rec)
+    }
+
+    #[test]
+    fn sampling_matches_eval() {
+        let mut rng = Random::new(2);
+        let c = Principled::constant;
+        let materials = [
+            // Plastic, coated car paint, velvet-like cloth and frosted glass.
+            Principled {
+                base_color: Color::new(0.2, 0.4, 0.9).into(),
+                ..Default::default()
+            },
+            Principled {
+                base_color: Color::new(0.7, 0.1, 0.1).into(),
+                metallic: c(0.5),
+                clearcoat: c(1.0),
+                clearcoat_roughness: c(0.2),
+                ..Default::default()
+            },
+            Principled {
+                roughness: c(0.9),
+                sheen: c(1.0),
+                ..Default::default()
+            },
+            Principled {
+                base_color: Color::new(1.0, 1.0, 1.0).into(),
+                roughness: c(0.6),
+                transmission: c(1.0),
+                ..Default::default()
+            },
+        ];
+        for material in materials {
+            let (r_in, rec) = record(Arc::new(material));
+            let pdf = match rec.mat_ptr.scatter(&r_in, &rec, &mut rng) {
+                Some(ScatterRecord::Pdf(pdf)) => pdf,
+                _ => panic!("expected a pdf"),
+            };
+            let f = |dir: &Vec3| {
+                let r = Ray::new(rec.p.clone(), dir.clone(), 0.0);
+                rec.mat_ptr.eval(&r_in, &rec, &r)
+            };
+
+            let n = 300_000;
+            let (mut sampled, mut uniform) = (Color::default(), Color::default());
+            for _ in 0..n {
+                let dir = pdf.generate(&mut rng);
+                let p = pdf.value(&dir, &mut rng);
+                if p > 0.0 {
+                    sampled += f(&dir) / p;
+                }
+                let dir = UniformSpherePdf.generate(&mut rng);
+                uniform += f(&dir) / UniformSpherePdf.value(&dir, &mut rng);
+            }
+            let (sampled, uniform) = (sampled / n as f64, uniform / n as f64);
+            let error = &sampled.0 - &uniform.0;
+            assert!(error.length() < 0.03, "{:?} {:?}", sampled, uniform);
+            let max = sampled.0.x.max(sampled.0.y).max(sampled.0.z);
+            assert!(max <= 1.0, "{:?}", sampled);
+        }
+    }
+
+    #[test]
+    fn metallic_is_a_conductor() {
+        let gold = Color::new(1.0, 0.78, 0.34);
+        let principled = Principled {
+            base_color: gold.clone().into(),
+            metallic: Principled::constant(1.0),
+            roughness: Principled::constant(0.6),
+            ..Default::default()
+        };
+        let (r_in, rec) = record(Arc::new(principled));
+        let conductor = Conductor::schlick(gold, 0.6);
+        let mut rng = Random::new(4);
+        for _ in 0..100 {
+            let r = Ray::new(rec.p.clone(), Vec3::random_unit_vector(&mut rng), 0.0);
+            let a = rec.mat_ptr.eval(&r_in, &rec, &r);
+            let b = conductor.eval(&r_in, &rec, &r);
+            assert!((&a.0 - &b.0).length() < 1e-12, "{:?} {:?}", a, b);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use std::sync::Arc;
+
+use super::dielectric::fresnel;
+use crate::onb::Onb;
+use crate::pdf::{Ggx, Pdf};
+use crate::texture::SolidColor;
+use crate::{Color, HitRecord, Material, Random, Ray, ScatterRecord, TexturePtr, Vec3};
+
+/// Frosted glass: a GGX microfacet interface that both reflects and
+/// refracts, choosing between the two by Fresnel reflectance. Like
+/// `Dielectric`, radiance is not rescaled by the change of index; the
+/// factors cancel for closed objects seen from outside.
+#[derive(Clone)]
+pub struct RoughDielectric {
+    ir: f64,
+    /// Perceptual roughness, read from the luminance of the texture.
+    roughness: TexturePtr,
+}
+
+impl RoughDielectric {
+    pub fn new(ir: f64, roughness: f64) -> Self {
+        Self::with_texture(
+            ir,
+            Arc::new(SolidColor::new(Color::new(roughness, roughness, roughness))),
+        )
+    }
+
+    pub fn with_texture(ir: f64, roughness: TexturePtr) -> Self {
+        Self { ir, roughness }
+    }
+
+    fn lobes(&self, r_in: &Ray, rec: &HitRecord) -> Lobes {
+        let ggx = Ggx::new(self.roughness.value(rec.u, rec.v, &rec.p).luminance());
+        let eta = if rec.front_face {
+            self.ir
+        } else {
+            1.0 / self.ir
+        };
+        Lobes::new(&-&r_in.dir, &rec.normal, ggx, eta)
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord, rng: &mut Random) -> Option<ScatterRecord> {
+        let lobes = self.lobes(r_in, rec);
+        if lobes.wo.z <= 0.0 {
+            return None;
+        }
+        if !lobes.ggx.is_smooth() {
+            return Some(ScatterRecord::Pdf(Box::new(lobes)));
+        }
+
+        let n = Vec3::new(0.0, 0.0, 1.0);
+        let direction = lobes.scatter_about(&n, rng);
+        Some(ScatterRecord::Specular {
+            attenuation: Color::new(1.0, 1.0, 1.0),
+            ray: Ray::new(rec.p.clone(), lobes.uvw.local(&direction), r_in.time),
+        })
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray) -> Color {
+        let f = self.lobes(r_in, rec).eval(&scattered.dir);
+        Color::new(f, f, f)
+    }
+}
+
+/// Reflection and transmission lobes in the frame of the normal on the
+/// side of `wo`, which doubles as their sampling density.
+pub(super) struct Lobes {
+    uvw: Onb,
+    /// Towards the viewer, in the local frame.
+    wo: Vec3,
+    ggx: Ggx,
+    /// Index of refraction behind the surface over that in front.
+    eta: f64,
+}
+
+impl Lobes {
+    /// `eta` is the index of refraction behind the surface over that in
+    /// front, where `n` is.
+    pub(super) fn new(wo: &Vec3, n: &Vec3, ggx: Ggx, eta: f64) -> Self {
+        let uvw = Onb::build_from_w(n);
+        let wo = uvw.to_local(&wo.unit_vector());
+        Self { uvw, wo, ggx, eta }
+    }
+
+    /// Reflects or refracts `wo` about the microfacet normal `h`, picking
+    /// reflection with the Fresnel probability. Returns the zero vector,
+    /// whose density is zero, when the direction ends up on the wrong side
+    /// of the surface for the chosen lobe.
+    fn scatter_about(&self, h: &Vec3, rng: &mut Random) -> Vec3 {
+        let incoming = -&self.wo;
+        let wi = if rng.unit_f64() < fresnel(self.wo.dot(h), self.eta) {
+            let wi = incoming.reflect(h);
+            (wi.z > 0.0).then_some(wi)
+        } else {
+            let wi = incoming.refract(h, 1.0 / self.eta);
+            (wi.z < 0.0).then_some(wi)
+        };
+        wi.unwrap_or_default()
+    }
+
+    /// Microfacet normal through which `wo` scatters to `wi`, facing `wo`,
+    /// or `None` if no visible microfacet does.
+    fn half_vector(&self, wi: &Vec3) -> Option<Vec3> {
+        let wo = &self.wo;
+        let reflected = wi.z > 0.0;
+        let h = if reflected {
+            wo + wi
+        } else {
+            wo + self.eta * wi
+        };
+        if h.near_zero() {
+            return None;
+        }
+        let h = h.unit_vector();
+        let h = if h.z < 0.0 { -h } else { h };
+        let valid = wo.dot(&h) > 0.0 && (reflected || wi.dot(&h) < 0.0);
+        valid.then_some(h)
+    }
+
+    /// BSDF times cosine for light leaving along `direction`.
+    pub(super) fn eval(&self, direction: &Vec3) -> f64 {
+        if direction.near_zero() {
+            return 0.0;
+        }
+        let wi = &self.uvw.to_local(&direction.unit_vector());
+        let (wo, ggx) = (&self.wo, &self.ggx);
+        let h = match self.half_vector(wi) {
+            Some(h) => h,
+            None => return 0.0,
+        };
+        let f = fresnel(wo.dot(&h), self.eta);
+        let dg = ggx.d(&h) * ggx.g2(wo, wi);
+        if wi.z > 0.0 {
+            f * dg / (4.0 * wo.z)
+        } else {
+            let denom = wi.dot(&h) + wo.dot(&h) / self.eta;
+            (1.0 - f) * dg * (wi.dot(&h) * wo.dot(&h)).abs() / (wo.z * denom * denom)
+        }
+    }
+}
+
+impl Pdf for Lobes {
+    fn value(&self, direction: &Vec3, _: &mut Random) -> f64 {
+        if direction.near_zero() {
+            return 0.0;
+        }
+        let wi = self.uvw.to_local(&direction.unit_vector());
+        let h = match self.half_vector(&wi) {
+            Some(h) => h,
+            None => return 0.0,
+        };
+        let wo_h = self.wo.dot(&h);
+        let f = fresnel(wo_h, self.eta);
+        let visible = self.ggx.visible_d(&self.wo, &h);
+        if wi.z > 0.0 {
+            // Jacobian of reflection about h.
+            f * visible / (4.0 * wo_h)
+        } else {
+            // Jacobian of refraction through h.
+            let denom = wi.dot(&h) + wo_h / self.eta;
+            (1.0 - f) * visible * wi.dot(&h).abs() / (denom * denom)
+        }
+    }
+
+    fn generate(&self, rng: &mut Random) -> Vec3 {
+        let h = self.ggx.sample_visible(&self.wo, rng);
+        self.uvw.local(&self.scatter_about(&h, rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf::UniformSpherePdf;
+    use crate::Point3;
+
+    #[test]
+    fn sampling_matches_eval() {
+        let mut rng = Random::new(11);
+        let r_in = Ray::new(Point3::new(-1.0, 1.0, 0.3), Vec3::new(1.0, -1.0, -0.3), 0.0);
+        for (roughness, outward) in [(0.5, 1.0), (0.8, 1.0), (0.5, -1.0)] {
+            // Entering the glass, or leaving it for -1.
+            let rec = HitRecord::new(
+                Point3::default(),
+                1.0,
+                0.0,
+                0.0,
+                &r_in,
+                Vec3::new(0.0, outward, 0.0),
+                Arc::new(RoughDielectric::new(1.5, roughness)),
+            );
+            let pdf = match rec.mat_ptr.scatter(&r_in, &rec, &mut rng) {
+                Some(ScatterRecord::Pdf(pdf)) => pdf,
+                _ => panic!("expected a pdf"),
+            };
+            let f = |dir: &Vec3| {
+                let r = Ray::new(rec.p.clone(), dir.clone(), 0.0);
+                rec.mat_ptr.eval(&r_in, &rec, &r).0.x
+            };
+
+            let n = 400_000;
+            let (mut sampled, mut transmitted) = (0.0, 0.0);
+            let (mut uniform, mut integral) = (0.0, 0.0);
+            for _ in 0..n {
+                let dir = pdf.generate(&mut rng);
+                let p = pdf.value(&dir, &mut rng);
+                if p > 0.0 {
+                    let weight = f(&dir) / p;
+                    // Each sample keeps at most its energy.
+                    assert!(weight <= 1.0 + 1e-9, "{}", weight);
+                    sampled += weight;
+                    if dir.y < 0.0 {
+                        transmitted += weight;
+                    }
+                }
+                let dir = UniformSpherePdf.generate(&mut rng);
+                let q = UniformSpherePdf.value(&dir, &mut rng);
+                uniform += f(&dir) / q;
+                integral += pdf.value(&dir, &mut rng) / q;
+            }
+            let n = n as f64;
+            let (sampled, transmitted, uniform, integral) =
+                (sampled / n, transmitted / n, uniform / n, integral / n);
+            assert!((sampled - uniform).abs() < 0.04, "{} {}", sampled, uniform);
+            assert!(sampled > 0.7 && integral < 1.03, "{} {}", sampled, integral);
+            // Most light gets through when entering; leaving at this angle
+            // is past the critical angle for much of the lobe.
+            if outward > 0.0 {
+                assert!(transmitted > 0.8 * sampled, "{} {}", transmitted, sampled);
+            }
+        }
+    }
+
+    #[test]
+    fn fresnel_limits() {
+        let r0 = (0.5f64 / 2.5).powi(2);
+        assert!((fresnel(1.0, 1.5) - r0).abs() < 1e-12);
+        assert!((fresnel(1.0, 1.0 / 1.5) - r0).abs() < 1e-12);
+        assert_eq!(fresnel(0.5, 1.0 / 1.5), 1.0);
+        assert!(fresnel(1e-9, 1.5) > 0.999);
+    }
+}
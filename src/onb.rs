@@ -0,0 +1,30 @@
+use crate::Vec3;
+
+/// Orthonormal basis with `w` along a given direction.
+#[derive(Debug, Clone)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn build_from_w(n: &Vec3) -> Self {
+        let w = n.unit_vector();
+        let a = if w.x.abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = w.cross(&a).unit_vector();
+        let u = w.cross(&v);
+        Self { u, v, w }
+    }
+    pub fn local(&self, a: &Vec3) -> Vec3 {
+        a.x * &self.u + a.y * &self.v + a.z * &self.w
+    }
+    /// Coordinates of `a` in this basis; the inverse of `local`.
+    pub fn to_local(&self, a: &Vec3) -> Vec3 {
+        Vec3::new(a.dot(&self.u), a.dot(&self.v), a.dot(&self.w))
+    }
+}
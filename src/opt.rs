@@ -1,6 +1,9 @@
+use std::path::PathBuf;
+
 use structopt::clap::arg_enum;
 use structopt::StructOpt;
 
+use crate::tone_map::{Aces, Filmic, Linear, Reinhard, ToneMapPtr, ToneMapping, Transfer};
 use crate::{scene::Scene, Random};
 
 #[derive(Debug, StructOpt)]
@@ -9,13 +12,51 @@ pub struct Opt {
     #[structopt(short = "w", long, default_value = "600")]
     pub image_width: usize,
 
-    /// Number of samples per pixel for each thread
-    #[structopt(short = "s", long = "samples", default_value = "64")]
+    /// Number of samples per pixel
+    #[structopt(short = "s", long = "samples", default_value = "512")]
     pub samples_per_pixel: usize,
 
-    /// Scenes (random, twospheres, twoperlinspheres, earth, simplelight, cornellbox, cornellsmoke, finalscene, triangle, teapot)
+    /// Maximum number of bounces per path
+    #[structopt(long, default_value = "50")]
+    pub max_depth: i32,
+
+    /// Scenes (random, twospheres, twoperlinspheres, earth, simplelight, cornellbox, cornellsmoke, finalscene, triangle, teapot, teapots)
     #[structopt(default_value = "random")]
     pub scene: SceneSelector,
+
+    /// Scene file (.ron, .gltf or .glb); overrides the built-in scene
+    #[structopt(long, parse(from_os_str))]
+    pub scene_file: Option<PathBuf>,
+
+    /// Output image file (png, jpg, tiff, bmp, ppm, or linear hdr, pfm, exr); writes PPM to stdout if omitted
+    #[structopt(short = "o", long, parse(from_os_str))]
+    pub output: Option<PathBuf>,
+
+    /// Seed for scene generation and sampling; random if omitted
+    #[structopt(long)]
+    pub seed: Option<u64>,
+
+    /// Exposure adjustment in stops, applied before tone mapping
+    #[structopt(long, default_value = "0", allow_hyphen_values = true)]
+    pub exposure: f64,
+
+    /// Tone mapping operator (linear, reinhard, aces, filmic)
+    #[structopt(long, default_value = "linear")]
+    pub tone_map: ToneMapSelector,
+
+    /// Transfer curve for 8-bit output (gamma2, srgb)
+    #[structopt(long, default_value = "gamma2")]
+    pub transfer: TransferSelector,
+}
+
+impl Opt {
+    pub fn tone_mapping(&self) -> ToneMapping {
+        ToneMapping::new(
+            self.exposure,
+            self.tone_map.tone_map(),
+            self.transfer.transfer(),
+        )
+    }
 }
 
 arg_enum! {
@@ -31,6 +72,7 @@ arg_enum! {
         FinalScene,
         Triangle,
         Teapot,
+        Teapots,
     }
 }
 
@@ -47,6 +89,45 @@ impl SceneSelector {
             SceneSelector::FinalScene => Scene::final_scene(rng),
             SceneSelector::Triangle => Scene::triangle(rng),
             SceneSelector::Teapot => Scene::teapot(rng),
+            SceneSelector::Teapots => Scene::teapots(rng),
+        }
+    }
+}
+
+arg_enum! {
+    #[derive(Debug, Copy, Clone)]
+    pub enum ToneMapSelector {
+        Linear,
+        Reinhard,
+        Aces,
+        Filmic,
+    }
+}
+
+impl ToneMapSelector {
+    pub fn tone_map(&self) -> ToneMapPtr {
+        match self {
+            ToneMapSelector::Linear => Box::new(Linear),
+            ToneMapSelector::Reinhard => Box::new(Reinhard::default()),
+            ToneMapSelector::Aces => Box::new(Aces),
+            ToneMapSelector::Filmic => Box::new(Filmic::default()),
+        }
+    }
+}
+
+arg_enum! {
+    #[derive(Debug, Copy, Clone)]
+    pub enum TransferSelector {
+        Gamma2,
+        Srgb,
+    }
+}
+
+impl TransferSelector {
+    pub fn transfer(&self) -> Transfer {
+        match self {
+            TransferSelector::Gamma2 => Transfer::Gamma2,
+            TransferSelector::Srgb => Transfer::Srgb,
         }
     }
 }
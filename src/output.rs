@@ -0,0 +1,166 @@
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::bail;
+use image::codecs::hdr::HdrEncoder;
+use image::codecs::pnm::{PnmEncoder, PnmSubtype, SampleEncoding};
+use image::{ColorType, ImageFormat, Rgb, RgbImage};
+
+use crate::tone_map::ToneMapping;
+use crate::Color;
+
+/// Rows of pixels, top row first.
+pub type Picture = Vec<Vec<Color>>;
+
+pub fn to_rgb_image(pic: &Picture, tone_mapping: &ToneMapping) -> RgbImage {
+    let height = pic.len() as u32;
+    let width = pic.first().map_or(0, Vec::len) as u32;
+    RgbImage::from_fn(width, height, |i, j| {
+        Rgb(tone_mapping.rgb(&pic[j as usize][i as usize]))
+    })
+}
+
+fn linear_rgb(c: &Color) -> (f32, f32, f32) {
+    (c.0.x as f32, c.0.y as f32, c.0.z as f32)
+}
+
+/// Writes `pic` to `path`, choosing the encoder from the file extension.
+///
+/// `.hdr`, `.pfm` and `.exr` keep the linear radiance as floats; every other
+/// format goes through `tone_mapping` to 8 bits.
+pub fn save(
+    pic: &Picture,
+    path: impl AsRef<Path>,
+    tone_mapping: &ToneMapping,
+) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    let ext = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase);
+    match ext.as_deref() {
+        Some("hdr") => save_hdr(pic, path),
+        Some("pfm") => save_pfm(pic, path),
+        Some("exr") => save_exr(pic, path),
+        _ => save_ldr(pic, path, tone_mapping),
+    }
+}
+
+fn save_hdr(pic: &Picture, path: &Path) -> anyhow::Result<()> {
+    let height = pic.len();
+    let width = pic.first().map_or(0, Vec::len);
+    let pixels: Vec<_> = pic
+        .iter()
+        .flatten()
+        .map(|c| {
+            let (r, g, b) = linear_rgb(c);
+            Rgb([r, g, b])
+        })
+        .collect();
+    let out = BufWriter::new(File::create(path)?);
+    HdrEncoder::new(out).encode(&pixels, width, height)?;
+    Ok(())
+}
+
+fn save_pfm(pic: &Picture, path: &Path) -> anyhow::Result<()> {
+    let height = pic.len();
+    let width = pic.first().map_or(0, Vec::len);
+    let mut out = BufWriter::new(File::create(path)?);
+    // Negative scale marks little-endian samples; rows are stored bottom to top.
+    write!(out, "PF\n{} {}\n-1.0\n", width, height)?;
+    for row in pic.iter().rev() {
+        for c in row {
+            let (r, g, b) = linear_rgb(c);
+            for v in &[r, g, b] {
+                out.write_all(&v.to_le_bytes())?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn save_exr(pic: &Picture, path: &Path) -> anyhow::Result<()> {
+    let height = pic.len();
+    let width = pic.first().map_or(0, Vec::len);
+    exr::prelude::write_rgb_file(path, width, height, |x, y| linear_rgb(&pic[y][x]))?;
+    Ok(())
+}
+
+fn save_ldr(pic: &Picture, path: &Path, tone_mapping: &ToneMapping) -> anyhow::Result<()> {
+    let img = to_rgb_image(pic, tone_mapping);
+    match ImageFormat::from_path(path)? {
+        ImageFormat::Pnm => {
+            let out = BufWriter::new(File::create(path)?);
+            PnmEncoder::new(out)
+                .with_subtype(PnmSubtype::Pixmap(SampleEncoding::Binary))
+                .encode(
+                    img.as_raw().as_slice(),
+                    img.width(),
+                    img.height(),
+                    ColorType::Rgb8,
+                )?;
+        }
+        format @ (ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Tiff | ImageFormat::Bmp) => {
+            img.save_with_format(path, format)?
+        }
+        format => bail!("Unsupported output format: {:?}", format),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// Directory of test `name` in this process, so concurrent runs do not
+    /// share files.
+    fn test_dir(name: &str) -> anyhow::Result<PathBuf> {
+        let dir = std::env::temp_dir().join(format!("raytracing-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    #[test]
+    fn save_and_reload() -> anyhow::Result<()> {
+        let pic = vec![
+            vec![Color::new(1.0, 0.0, 0.0); 3],
+            vec![Color::new(0.0, 0.0, 1.0); 3],
+        ];
+        let dir = test_dir("save-and-reload")?;
+        for ext in &["png", "jpg", "tiff", "bmp", "ppm"] {
+            let path = dir.join(format!("picture.{}", ext));
+            save(&pic, &path, &ToneMapping::default())?;
+            let img = image::open(&path)?.to_rgb8();
+            assert_eq!(img.dimensions(), (3, 2));
+        }
+        assert!(save(&pic, dir.join("picture.gif"), &ToneMapping::default()).is_err());
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn save_hdr_keeps_radiance() -> anyhow::Result<()> {
+        let pic = vec![vec![Color::new(15.0, 0.5, 0.0), Color::new(0.25, 4.0, 1.0)]];
+        let dir = test_dir("save-hdr")?;
+        for ext in &["hdr", "pfm", "exr"] {
+            let path = dir.join(format!("picture.{}", ext));
+            save(&pic, &path, &ToneMapping::default())?;
+            assert!(std::fs::metadata(&path)?.len() > 0);
+        }
+
+        let path = dir.join("picture.pfm");
+        let bytes = std::fs::read(&path)?;
+        let header = b"PF\n2 1\n-1.0\n";
+        assert!(bytes.starts_with(header));
+        let mut red = [0; 4];
+        red.copy_from_slice(&bytes[header.len()..header.len() + 4]);
+        assert_eq!(f32::from_le_bytes(red), 15.0);
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{Random, Vec3};
+
+/// Probability density over directions, measured in solid angle.
+pub trait Pdf {
+    fn value(&self, direction: &Vec3, rng: &mut Random) -> f64;
+    fn generate(&self, rng: &mut Random) -> Vec3;
+}
+
+pub type PdfPtr = Box<dyn Pdf>;
+
+/// Multiple importance sampling weight for a sample drawn from `f`.
+pub fn power_heuristic(pdf_f: f64, pdf_g: f64) -> f64 {
+    let f2 = pdf_f * pdf_f;
+    let g2 = pdf_g * pdf_g;
+    if f2 + g2 == 0.0 {
+        0.0
+    } else {
+        f2 / (f2 + g2)
+    }
+}
+
+pub mod cosine;
+pub mod fuzz;
+pub mod ggx;
+pub mod hittable_pdf;
+pub mod mixture;
+pub mod uniform_sphere;
+
+pub use cosine::CosinePdf;
+pub use fuzz::FuzzPdf;
+pub use ggx::{Ggx, GgxPdf};
+pub use hittable_pdf::HittablePdf;
+pub use mixture::MixturePdf;
+pub use uniform_sphere::UniformSpherePdf;
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::hittable::{Sphere, XZRect};
+    use crate::material::Lambertian;
+    use crate::{Color, Point3};
+
+    const N: usize = 100_000;
+
+    #[test]
+    fn cosine_pdf_is_normalized() {
+        let mut rng = Random::new(1);
+        let pdf = CosinePdf::new(&Vec3::new(0.3, 1.0, -0.2));
+        let sphere = UniformSpherePdf;
+        let integral: f64 = (0..N)
+            .map(|_| pdf.value(&sphere.generate(&mut rng), &mut rng))
+            .sum::<f64>()
+            * 4.0
+            * PI
+            / N as f64;
+        assert!((integral - 1.0).abs() < 0.02, "{}", integral);
+    }
+
+    #[test]
+    fn fuzz_pdf_matches_solid_angle() {
+        let mut rng = Random::new(2);
+        let pdf = FuzzPdf::new(&Vec3::new(1.0, 1.0, 0.0), 0.5);
+        // The ball of radius 0.5 at distance 1 subtends a 30 degree cone.
+        let expected = 2.0 * PI * (1.0 - (PI / 6.0).cos());
+        let estimate: f64 = (0..N)
+            .map(|_| 1.0 / pdf.value(&pdf.generate(&mut rng), &mut rng))
+            .sum::<f64>()
+            / N as f64;
+        assert!(
+            (estimate - expected).abs() / expected < 0.05,
+            "{}",
+            estimate
+        );
+    }
+
+    #[test]
+    fn hittable_pdf_matches_solid_angle() {
+        let mut rng = Random::new(3);
+        let mat = Arc::new(Lambertian::with_color(Color::new(1.0, 1.0, 1.0)));
+        let rect = XZRect::new(-1.0, 1.0, -1.0, 1.0, 2.0, mat);
+        let pdf = HittablePdf::new(&rect, Point3::default());
+
+        // Monte Carlo estimate of the solid angle from uniform directions.
+        let sphere = UniformSpherePdf;
+        let covered = (0..N)
+            .filter(|_| pdf.value(&sphere.generate(&mut rng), &mut rng) > 0.0)
+            .count();
+        let expected = 4.0 * PI * covered as f64 / N as f64;
+
+        let estimate: f64 = (0..N)
+            .map(|_| 1.0 / pdf.value(&pdf.generate(&mut rng), &mut rng))
+            .sum::<f64>()
+            / N as f64;
+        assert!((estimate - expected).abs() / expected < 0.05);
+    }
+
+    #[test]
+    fn hittable_pdf_inside_sphere() {
+        let mut rng = Random::new(4);
+        let mat = Arc::new(Lambertian::with_color(Color::new(1.0, 1.0, 1.0)));
+        let sphere = Sphere::new(Point3::new(0.5, 0.0, 0.0), 1.0, mat);
+        let pdf = HittablePdf::new(&sphere, Point3::default());
+        for _ in 0..100 {
+            let v = pdf.generate(&mut rng);
+            assert!((pdf.value(&v, &mut rng) - 1.0 / (4.0 * PI)).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use std::f64::consts::PI;
+
+use super::Pdf;
+use crate::onb::Onb;
+use crate::{Random, Vec3};
+
+#[derive(Debug, Clone)]
+pub struct CosinePdf {
+    uvw: Onb,
+}
+
+impl CosinePdf {
+    pub fn new(w: &Vec3) -> Self {
+        Self {
+            uvw: Onb::build_from_w(w),
+        }
+    }
+}
+
+impl Pdf for CosinePdf {
+    fn value(&self, direction: &Vec3, _: &mut Random) -> f64 {
+        let cosine = direction.unit_vector().dot(&self.uvw.w);
+        (cosine / PI).max(0.0)
+    }
+
+    fn generate(&self, rng: &mut Random) -> Vec3 {
+        self.uvw.local(&Vec3::random_cosine_direction(rng))
+    }
+}
@@ -0,0 +1,45 @@
+use std::f64::consts::PI;
+
+use super::Pdf;
+use crate::{Random, Vec3};
+
+/// Directions of `reflected + fuzz * p` with `p` uniform in the unit ball.
+#[derive(Debug, Clone)]
+pub struct FuzzPdf {
+    reflected: Vec3,
+    fuzz: f64,
+}
+
+impl FuzzPdf {
+    pub fn new(reflected: &Vec3, fuzz: f64) -> Self {
+        Self {
+            reflected: reflected.unit_vector(),
+            fuzz,
+        }
+    }
+
+    /// Ball volume along the ray through `direction`, weighted by `t^2`.
+    pub fn density(&self, direction: &Vec3) -> f64 {
+        let c = direction.unit_vector().dot(&self.reflected);
+        let discriminant = c * c - 1.0 + self.fuzz.powi(2);
+        if discriminant < 0.0 {
+            return 0.0;
+        }
+        let t1 = c + discriminant.sqrt();
+        let t0 = (c - discriminant.sqrt()).max(0.0);
+        if t1 <= 0.0 {
+            return 0.0;
+        }
+        (t1.powi(3) - t0.powi(3)) / (4.0 * PI * self.fuzz.powi(3))
+    }
+}
+
+impl Pdf for FuzzPdf {
+    fn value(&self, direction: &Vec3, _: &mut Random) -> f64 {
+        self.density(direction)
+    }
+
+    fn generate(&self, rng: &mut Random) -> Vec3 {
+        &self.reflected + self.fuzz * Vec3::random_in_unit_sphere(rng)
+    }
+}
@@ -0,0 +1,144 @@
+use std::f64::consts::PI;
+
+use super::Pdf;
+use crate::onb::Onb;
+use crate::{Random, Vec3};
+
+/// Trowbridge-Reitz (GGX) distribution of microfacet normals. Directions
+/// are in a local frame with the macro surface normal along z.
+#[derive(Debug, Clone, Copy)]
+pub struct Ggx {
+    alpha: f64,
+}
+
+impl Ggx {
+    /// Smallest `alpha` worth sampling; smoother surfaces are mirrors.
+    pub const MIN_ALPHA: f64 = 1e-3;
+
+    /// From perceptual roughness in `[0, 1]`, with `alpha = roughness^2`.
+    pub fn new(roughness: f64) -> Self {
+        let r = roughness.clamp(0.0, 1.0);
+        Self {
+            alpha: (r * r).max(Self::MIN_ALPHA),
+        }
+    }
+
+    pub fn is_smooth(&self) -> bool {
+        self.alpha <= Self::MIN_ALPHA
+    }
+
+    /// Density of microfacet normals `h` per projected area.
+    pub fn d(&self, h: &Vec3) -> f64 {
+        if h.z <= 0.0 {
+            return 0.0;
+        }
+        let a2 = self.alpha * self.alpha;
+        let t = h.z * h.z * (a2 - 1.0) + 1.0;
+        a2 / (PI * t * t)
+    }
+
+    fn lambda(&self, w: &Vec3) -> f64 {
+        let cos2 = w.z * w.z;
+        if cos2 == 0.0 {
+            return f64::INFINITY;
+        }
+        let tan2 = (1.0 - cos2).max(0.0) / cos2;
+        ((1.0 + self.alpha * self.alpha * tan2).sqrt() - 1.0) / 2.0
+    }
+
+    /// Smith masking for one direction.
+    pub fn g1(&self, w: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Height-correlated Smith masking-shadowing.
+    pub fn g2(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Cosine-weighted BSDF for reflection off the microfacets with a
+    /// Fresnel factor of one, `D G / (4 cos_o)`, for world-space unit
+    /// directions about the normal `n`.
+    pub fn reflection(&self, wo: &Vec3, wi: &Vec3, n: &Vec3) -> f64 {
+        let (cos_o, cos_i) = (wo.dot(n), wi.dot(n));
+        if cos_o <= 0.0 || cos_i <= 0.0 {
+            return 0.0;
+        }
+        // Only angles to the normal matter, so one axis of a local frame
+        // is enough.
+        let local = |w: &Vec3| {
+            let c = w.dot(n).clamp(-1.0, 1.0);
+            Vec3::new((1.0 - c * c).sqrt(), 0.0, c)
+        };
+        let h = (wo + wi).unit_vector();
+        self.d(&local(&h)) * self.g2(&local(wo), &local(wi)) / (4.0 * cos_o)
+    }
+
+    /// Density of microfacet normals visible from `wo`.
+    pub fn visible_d(&self, wo: &Vec3, h: &Vec3) -> f64 {
+        if wo.z <= 0.0 {
+            return 0.0;
+        }
+        self.g1(wo) * wo.dot(h).max(0.0) * self.d(h) / wo.z
+    }
+
+    /// Samples a normal visible from `wo` (Heitz 2018).
+    pub fn sample_visible(&self, wo: &Vec3, rng: &mut Random) -> Vec3 {
+        let a = self.alpha;
+        // Stretch to the hemisphere configuration.
+        let vh = Vec3::new(a * wo.x, a * wo.y, wo.z).unit_vector();
+        let len2 = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if len2 > 0.0 {
+            Vec3::new(-vh.y, vh.x, 0.0) / len2.sqrt()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = vh.cross(&t1);
+
+        // A point on the disk, warped towards the visible half.
+        let r = rng.unit_f64().sqrt();
+        let phi = 2.0 * PI * rng.unit_f64();
+        let (p1, p2) = (r * phi.cos(), r * phi.sin());
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * p2;
+        let nh = p1 * &t1 + p2 * &t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * &vh;
+
+        Vec3::new(a * nh.x, a * nh.y, nh.z.max(0.0)).unit_vector()
+    }
+}
+
+/// Reflections of `wo` about GGX normals visible from it.
+#[derive(Debug, Clone)]
+pub struct GgxPdf {
+    uvw: Onb,
+    /// Towards the viewer, in the local frame.
+    wo: Vec3,
+    ggx: Ggx,
+}
+
+impl GgxPdf {
+    /// `wo` points away from the surface with normal `n`.
+    pub fn new(wo: &Vec3, n: &Vec3, ggx: Ggx) -> Self {
+        let uvw = Onb::build_from_w(n);
+        let wo = uvw.to_local(&wo.unit_vector());
+        Self { uvw, wo, ggx }
+    }
+}
+
+impl Pdf for GgxPdf {
+    fn value(&self, direction: &Vec3, _: &mut Random) -> f64 {
+        let wi = self.uvw.to_local(&direction.unit_vector());
+        let h = &self.wo + &wi;
+        if wi.z <= 0.0 || h.near_zero() {
+            return 0.0;
+        }
+        let h = h.unit_vector();
+        // Jacobian of reflection about h, 1 / (4 wo.h).
+        self.ggx.visible_d(&self.wo, &h) / (4.0 * self.wo.dot(&h))
+    }
+
+    fn generate(&self, rng: &mut Random) -> Vec3 {
+        let h = self.ggx.sample_visible(&self.wo, rng);
+        self.uvw.local(&(-&self.wo).reflect(&h))
+    }
+}
@@ -0,0 +1,24 @@
+use super::Pdf;
+use crate::{Hittable, Point3, Random, Vec3};
+
+/// Directions from `origin` towards points on `object`.
+pub struct HittablePdf<'a> {
+    object: &'a dyn Hittable,
+    origin: Point3,
+}
+
+impl<'a> HittablePdf<'a> {
+    pub fn new(object: &'a dyn Hittable, origin: Point3) -> Self {
+        Self { object, origin }
+    }
+}
+
+impl Pdf for HittablePdf<'_> {
+    fn value(&self, direction: &Vec3, rng: &mut Random) -> f64 {
+        self.object.pdf_value(&self.origin, direction, rng)
+    }
+
+    fn generate(&self, rng: &mut Random) -> Vec3 {
+        self.object.random(&self.origin, rng)
+    }
+}
@@ -0,0 +1,28 @@
+use super::Pdf;
+use crate::{Random, Vec3};
+
+/// Equal-weight mixture of two densities.
+pub struct MixturePdf<'a> {
+    p0: &'a dyn Pdf,
+    p1: &'a dyn Pdf,
+}
+
+impl<'a> MixturePdf<'a> {
+    pub fn new(p0: &'a dyn Pdf, p1: &'a dyn Pdf) -> Self {
+        Self { p0, p1 }
+    }
+}
+
+impl Pdf for MixturePdf<'_> {
+    fn value(&self, direction: &Vec3, rng: &mut Random) -> f64 {
+        0.5 * self.p0.value(direction, rng) + 0.5 * self.p1.value(direction, rng)
+    }
+
+    fn generate(&self, rng: &mut Random) -> Vec3 {
+        if rng.unit_f64() < 0.5 {
+            self.p0.generate(rng)
+        } else {
+            self.p1.generate(rng)
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use std::f64::consts::PI;
+
+use super::Pdf;
+use crate::{Random, Vec3};
+
+#[derive(Debug, Clone, Default)]
+pub struct UniformSpherePdf;
+
+impl Pdf for UniformSpherePdf {
+    fn value(&self, _: &Vec3, _: &mut Random) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn generate(&self, rng: &mut Random) -> Vec3 {
+        Vec3::random_unit_vector(rng)
+    }
+}
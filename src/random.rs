@@ -1,11 +1,26 @@
 use rand::distributions::{Standard, Uniform};
-use rand::{rngs::ThreadRng, seq::SliceRandom, thread_rng, Rng};
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_pcg::Pcg64;
 
 pub struct Random {
-    rng: ThreadRng,
+    rng: Pcg64,
 }
 
 impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self::with_stream(seed, 0)
+    }
+    /// Independent sequence `stream` of `seed`, e.g. one per pixel.
+    pub fn with_stream(seed: u64, stream: u64) -> Self {
+        // PCG streams that share a state and differ only in consecutive
+        // increments are correlated, so scramble the state too.
+        let hi = splitmix64(seed ^ splitmix64(stream));
+        let lo = splitmix64(stream ^ splitmix64(!seed));
+        let state = (hi as u128) << 64 | lo as u128;
+        Self {
+            rng: Pcg64::new(state, stream as u128),
+        }
+    }
     pub fn unit_f64(&mut self) -> f64 {
         self.rng.sample(Standard)
     }
@@ -21,13 +36,54 @@ impl Random {
         let distr = Uniform::new(min, max);
         self.rng.sample(distr)
     }
+    pub fn next_u64(&mut self) -> u64 {
+        self.rng.gen()
+    }
     pub fn shuffle<T>(&mut self, slice: &mut [T]) {
         slice.shuffle(&mut self.rng);
     }
 }
 
+/// Finalizer of SplitMix64, a bijective mix of the bits of `x`.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
 impl Default for Random {
+    /// Seeded from the operating system, so runs differ.
     fn default() -> Self {
-        Self { rng: thread_rng() }
+        Self {
+            rng: Pcg64::from_entropy(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = Random::with_stream(42, 7);
+        let mut b = Random::with_stream(42, 7);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn streams_differ() {
+        let a: Vec<_> = {
+            let mut rng = Random::with_stream(42, 0);
+            (0..8).map(|_| rng.next_u64()).collect()
+        };
+        let b: Vec<_> = {
+            let mut rng = Random::with_stream(42, 1);
+            (0..8).map(|_| rng.next_u64()).collect()
+        };
+        assert_ne!(a, b);
     }
 }
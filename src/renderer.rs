@@ -0,0 +1,252 @@
+use rayon::prelude::*;
+
+use crate::hittable::Hittable;
+use crate::output::Picture;
+use crate::pdf::{power_heuristic, HittablePdf, Pdf};
+use crate::scene::Scene;
+use crate::{Camera, Color, HitRecord, Random, Ray, ScatterRecord};
+
+const TILE_SIZE: usize = 16;
+
+/// Everything about a render that is not part of the scene itself.
+#[derive(Debug, Clone)]
+pub struct RenderSettings {
+    pub image_width: usize,
+    pub image_height: usize,
+    pub samples_per_pixel: usize,
+    pub max_depth: i32,
+    /// Shutter interval; rays are spread uniformly over `time0..time1`.
+    pub time0: f64,
+    pub time1: f64,
+    pub seed: u64,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            image_width: 600,
+            image_height: 400,
+            samples_per_pixel: 512,
+            max_depth: 50,
+            time0: 0.0,
+            time1: 1.0,
+            seed: 0,
+        }
+    }
+}
+
+impl RenderSettings {
+    /// Settings for an image `image_width` pixels wide, with the height
+    /// taken from the scene's aspect ratio.
+    pub fn for_scene(scene: &Scene, image_width: usize) -> Self {
+        Self {
+            image_width,
+            image_height: (image_width as f64 / scene.aspect_ratio) as usize,
+            ..Default::default()
+        }
+    }
+}
+
+pub struct Renderer<'a> {
+    scene: &'a Scene,
+    camera: Camera,
+    settings: RenderSettings,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(scene: &'a Scene, settings: RenderSettings) -> Self {
+        let aspect_ratio = settings.image_width as f64 / settings.image_height as f64;
+        let camera = Camera::with_scene(scene, aspect_ratio, settings.time0, settings.time1);
+        Self {
+            scene,
+            camera,
+            settings,
+        }
+    }
+
+    pub fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    pub fn render(&self) -> Picture {
+        self.render_with_progress(|_| {})
+    }
+
+    /// Renders tiles in parallel; rayon's work stealing balances uneven tiles.
+    /// `progress` is called with the number of pixels finished by each tile.
+    pub fn render_with_progress<F>(&self, progress: F) -> Picture
+    where
+        F: Fn(usize) + Sync,
+    {
+        let RenderSettings {
+            image_width: width,
+            image_height: height,
+            ..
+        } = self.settings;
+
+        let rendered: Vec<(Tile, Vec<Color>)> = tiles(height, width)
+            .into_par_iter()
+            .map(|tile| {
+                let mut pixels = Vec::with_capacity((tile.x1 - tile.x0) * (tile.y1 - tile.y0));
+                for y in tile.y0..tile.y1 {
+                    for i in tile.x0..tile.x1 {
+                        pixels.push(self.render_pixel(i, height - 1 - y));
+                    }
+                }
+                progress(pixels.len());
+                (tile, pixels)
+            })
+            .collect();
+
+        let mut pic = vec![vec![Color::default(); width]; height];
+        for (tile, pixels) in rendered {
+            let tile_width = tile.x1 - tile.x0;
+            for (y, row) in pixels.chunks(tile_width).enumerate() {
+                pic[tile.y0 + y][tile.x0..tile.x1].clone_from_slice(row);
+            }
+        }
+        pic
+    }
+
+    /// Averages the samples of pixel `(i, j)`, with `j` counted from the
+    /// bottom row.
+    pub fn render_pixel(&self, i: usize, j: usize) -> Color {
+        let settings = &self.settings;
+        let (width, height) = (settings.image_width, settings.image_height);
+        // One stream per pixel keeps the result independent of scheduling.
+        let mut rng = Random::with_stream(settings.seed, (j * width + i) as u64 + 1);
+        let mut color_pixel = Color::default();
+        for _ in 0..settings.samples_per_pixel {
+            let u = (i as f64 + rng.unit_f64()) / (width - 1) as f64;
+            let v = (j as f64 + rng.unit_f64()) / (height - 1) as f64;
+            let r = self.camera.get_ray(u, v, &mut rng);
+            color_pixel += self.ray_color(&r, settings.max_depth, None, &mut rng);
+        }
+        color_pixel / settings.samples_per_pixel as f64
+    }
+
+    /// `bsdf_pdf` is the density with which `r` was sampled from a BSDF, or
+    /// `None` for camera rays and specular bounces.
+    pub fn ray_color(&self, r: &Ray, depth: i32, bsdf_pdf: Option<f64>, rng: &mut Random) -> Color {
+        if depth <= 0 {
+            return Color::default();
+        }
+
+        let scene = self.scene;
+        let rec = match scene.world.hit(r, 0.001, f64::INFINITY, rng) {
+            Some(rec) => rec,
+            None => return scene.background.value(r),
+        };
+        let mut emmited = rec.mat_ptr.emmitted(rec.u, rec.v, &rec.p);
+        if let Some(bsdf_pdf) = bsdf_pdf {
+            if !scene.lights.is_empty() {
+                let light_pdf = scene.lights.pdf_value(&r.orig, &r.dir, rng);
+                emmited *= power_heuristic(bsdf_pdf, light_pdf);
+            }
+        }
+
+        match rec.mat_ptr.scatter(r, &rec, rng) {
+            None => emmited,
+            Some(ScatterRecord::Specular { attenuation, ray }) => {
+                emmited + attenuation * self.ray_color(&ray, depth - 1, None, rng)
+            }
+            Some(ScatterRecord::Pdf(pdf)) => {
+                let direct = self.sample_lights(r, &rec, pdf.as_ref(), rng);
+
+                let scattered = Ray::new(rec.p.clone(), pdf.generate(rng), r.time);
+                let pdf_val = pdf.value(&scattered.dir, rng);
+                if pdf_val <= 0.0 {
+                    return emmited + direct;
+                }
+                let indirect = rec.mat_ptr.eval(r, &rec, &scattered)
+                    * self.ray_color(&scattered, depth - 1, Some(pdf_val), rng)
+                    / pdf_val;
+
+                emmited + direct + indirect
+            }
+        }
+    }
+
+    /// Next event estimation: radiance arriving straight from a light sample,
+    /// weighted against BSDF sampling with the power heuristic.
+    fn sample_lights(&self, r: &Ray, rec: &HitRecord, bsdf: &dyn Pdf, rng: &mut Random) -> Color {
+        let lights = &self.scene.lights;
+        if lights.is_empty() {
+            return Color::default();
+        }
+
+        let light_pdf = HittablePdf::new(lights, rec.p.clone());
+        let shadow = Ray::new(rec.p.clone(), light_pdf.generate(rng), r.time);
+        let pdf_val = light_pdf.value(&shadow.dir, rng);
+        if pdf_val <= 0.0 {
+            return Color::default();
+        }
+        let f = rec.mat_ptr.eval(r, rec, &shadow);
+        if f == Color::default() {
+            return Color::default();
+        }
+
+        // Whatever the shadow ray hits first; occluders emit nothing.
+        let emmited = match self.scene.world.hit(&shadow, 0.001, f64::INFINITY, rng) {
+            Some(light_rec) => light_rec
+                .mat_ptr
+                .emmitted(light_rec.u, light_rec.v, &light_rec.p),
+            None => return Color::default(),
+        };
+        let weight = power_heuristic(pdf_val, bsdf.value(&shadow.dir, rng));
+        f * emmited * (weight / pdf_val)
+    }
+}
+
+/// A rectangular block of pixels, `x0..x1` by `y0..y1` with `y` counted
+/// from the top row.
+struct Tile {
+    x0: usize,
+    x1: usize,
+    y0: usize,
+    y1: usize,
+}
+
+fn tiles(height: usize, width: usize) -> Vec<Tile> {
+    (0..height)
+        .step_by(TILE_SIZE)
+        .flat_map(|y0| {
+            (0..width).step_by(TILE_SIZE).map(move |x0| Tile {
+                x0,
+                x1: (x0 + TILE_SIZE).min(width),
+                y0,
+                y1: (y0 + TILE_SIZE).min(height),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn render_is_deterministic() {
+        let scene = Scene::cornell_box(&mut Random::new(1));
+        let settings = RenderSettings {
+            image_width: 20,
+            image_height: 18,
+            samples_per_pixel: 2,
+            max_depth: 4,
+            seed: 7,
+            ..Default::default()
+        };
+        let renderer = Renderer::new(&scene, settings);
+
+        let done = AtomicUsize::new(0);
+        let pic = renderer.render_with_progress(|n| {
+            done.fetch_add(n, Ordering::Relaxed);
+        });
+        assert_eq!(done.into_inner(), 20 * 18);
+        assert_eq!(pic.len(), 18);
+        assert!(pic.iter().all(|row| row.len() == 20));
+        assert_eq!(pic, renderer.render());
+    }
+}
@@ -1,21 +1,29 @@
 use std::sync::Arc;
 
+use crate::algebra::Mat4;
 use crate::background::{dark, sky, BackgroundPtr};
 use crate::hittable::{
-    rotate_y, translate, BoxObj, BvhNode, ConstantMedium, HittableList, MovingSphere, Sphere,
-    Triangle, XYRect, XZRect, YZRect,
+    rotate_y, translate, BoxObj, BvhNode, ConstantMedium, HittableList, Instance, Instances,
+    MovingSphere, Sphere, Triangle, TriangleMesh, XYRect, XZRect, YZRect,
 };
 use crate::material::{Dielectric, DiffuseLight, Lambertian, Metal};
 use crate::texture::{Checker, ImageTexture, Marble};
-use crate::{Color, HittablePtr, Point3, Random, Vec3};
+use crate::{Color, HittablePtr, MaterialPtr, Point3, Random, Vec3};
+
+pub mod file;
+pub mod gltf_import;
 
 pub struct Scene {
     pub world: HittableList,
+    /// Emitters sampled directly by the integrator.
+    pub lights: HittableList,
     pub background: BackgroundPtr,
     pub lookfrom: Point3,
     pub lookat: Point3,
+    pub vup: Vec3,
     pub vfov: f64,
     pub aperture: f64,
+    pub focus_dist: f64,
     pub aspect_ratio: f64,
 }
 
@@ -23,11 +31,14 @@ impl Default for Scene {
     fn default() -> Self {
         Self {
             world: Default::default(),
+            lights: Default::default(),
             background: sky(),
             lookfrom: Point3::new(13.0, 2.0, 3.0),
             lookat: Point3::default(),
+            vup: Vec3::new(0.0, 1.0, 0.0),
             vfov: 20.0,
             aperture: 0.0,
+            focus_dist: 10.0,
             aspect_ratio: 3.0 / 2.0,
         }
     }
@@ -79,7 +90,7 @@ impl Scene {
                 }
             }
         }
-        let bvh = BvhNode::new(&mut objects, 0.0, 1.0, rng).unwrap();
+        let bvh = BvhNode::new(&objects, 0.0, 1.0).unwrap();
         world.add(Arc::new(bvh));
 
         world.add(Arc::new(Sphere::new(
@@ -176,10 +187,14 @@ impl Scene {
         world.add(Arc::new(Sphere::new(Point3::new(0.0, 2.0, 0.0), 2.0, mat)));
 
         let difflight = Arc::new(DiffuseLight::with_color(Color::new(4.0, 4.0, 4.0)));
-        world.add(Arc::new(XYRect::new(3.0, 5.0, 1.0, 3.0, -2.0, difflight)));
+        let light = Arc::new(XYRect::new(3.0, 5.0, 1.0, 3.0, -2.0, difflight));
+        world.add(light.clone());
+        let mut lights = HittableList::default();
+        lights.add(light);
 
         Scene {
             world,
+            lights,
             background: dark(),
             lookfrom: Point3::new(26.0, 3.0, 6.0),
             lookat: Point3::new(0.0, 2.0, 0.0),
@@ -197,7 +212,10 @@ impl Scene {
 
         world.add(Arc::new(YZRect::new(0., 555., 0., 555., 555., green)));
         world.add(Arc::new(YZRect::new(0., 555., 0., 555., 0., red)));
-        world.add(Arc::new(XZRect::new(213., 343., 227., 332., 554., light)));
+        let light = Arc::new(XZRect::new(213., 343., 227., 332., 554., light));
+        world.add(light.clone());
+        let mut lights = HittableList::default();
+        lights.add(light);
         world.add(Arc::new(XZRect::new(0., 555., 0., 555., 0., white.clone())));
         world.add(Arc::new(XZRect::new(
             0.,
@@ -235,6 +253,7 @@ impl Scene {
 
         Scene {
             world,
+            lights,
             background: dark(),
             lookfrom: Point3::new(278., 278., -800.),
             lookat: Point3::new(278., 278., 0.),
@@ -254,7 +273,10 @@ impl Scene {
 
         world.add(Arc::new(YZRect::new(0., 555., 0., 555., 555., green)));
         world.add(Arc::new(YZRect::new(0., 555., 0., 555., 0., red)));
-        world.add(Arc::new(XZRect::new(113., 443., 127., 432., 554., light)));
+        let light = Arc::new(XZRect::new(113., 443., 127., 432., 554., light));
+        world.add(light.clone());
+        let mut lights = HittableList::default();
+        lights.add(light);
         world.add(Arc::new(XZRect::new(0., 555., 0., 555., 0., white.clone())));
         world.add(Arc::new(XZRect::new(
             0.,
@@ -301,6 +323,7 @@ impl Scene {
 
         Scene {
             world,
+            lights,
             background: dark(),
             lookfrom: Point3::new(278., 278., -800.),
             lookat: Point3::new(278., 278., 0.),
@@ -336,10 +359,13 @@ impl Scene {
                 )));
             }
         }
-        world.add(Arc::new(BvhNode::new(&mut boxes1, 0.0, 1.0, rng).unwrap()));
+        world.add(Arc::new(BvhNode::new(&boxes1, 0.0, 1.0).unwrap()));
 
         let light = Arc::new(DiffuseLight::with_color(Color::new(7., 7., 7.)));
-        world.add(Arc::new(XZRect::new(123., 423., 147., 412., 554., light)));
+        let light = Arc::new(XZRect::new(123., 423., 147., 412., 554., light));
+        world.add(light.clone());
+        let mut lights = HittableList::default();
+        lights.add(light);
 
         let center0 = Point3::new(400., 400., 200.);
         let center1 = &center0 + Vec3::new(30.0, 0.0, 0.0);
@@ -409,15 +435,13 @@ impl Scene {
             )))
         }
         world.add(translate(
-            rotate_y(
-                Arc::new(BvhNode::new(&mut boxes2, 0.0, 1.0, rng).unwrap()),
-                15.,
-            ),
+            rotate_y(Arc::new(BvhNode::new(&boxes2, 0.0, 1.0).unwrap()), 15.),
             Vec3::new(-100., 270., 395.),
         ));
 
         Scene {
             world,
+            lights,
             background: dark(),
             lookfrom: Point3::new(478., 278., -600.),
             lookat: Point3::new(278., 278., 0.),
@@ -477,7 +501,7 @@ impl Scene {
         }
     }
 
-    pub fn teapot(rng: &mut Random) -> Self {
+    pub fn teapot(_: &mut Random) -> Self {
         let mut world = HittableList::default();
 
         let checker = Arc::new(Checker::with_color(
@@ -492,7 +516,7 @@ impl Scene {
         )));
 
         let pot_mat = Arc::new(Lambertian::with_color(Color::new(0.73, 0.73, 0.73)));
-        let pot = BvhNode::load("res/teapot.obj", 0.0, 1.0, pot_mat, rng).unwrap();
+        let pot = TriangleMesh::load("res/teapot.obj", pot_mat).unwrap();
 
         world.add(Arc::new(pot));
 
@@ -503,4 +527,55 @@ impl Scene {
             ..Default::default()
         }
     }
+
+    /// A thousand teapots sharing one mesh through instancing, each
+    /// instance picking one of three materials.
+    pub fn teapots(rng: &mut Random) -> Self {
+        let mut world = HittableList::default();
+
+        let checker = Arc::new(Checker::with_color(
+            Color::new(0.2, 0.3, 0.1),
+            Color::new(0.9, 0.9, 0.9),
+        ));
+        let ground_material = Arc::new(Lambertian::new(checker));
+        world.add(Arc::new(Sphere::new(
+            Point3::new(0.0, -1000.0, 0.0),
+            1000.0,
+            ground_material,
+        )));
+
+        let materials: Vec<MaterialPtr> = vec![
+            Arc::new(Lambertian::with_color(Color::new(0.73, 0.73, 0.73))),
+            Arc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 0.1)),
+            Arc::new(Dielectric::new(1.5)),
+        ];
+        let pot: HittablePtr =
+            Arc::new(TriangleMesh::load("res/teapot.obj", materials[0].clone()).unwrap());
+
+        let mut instances = Vec::new();
+        for a in -20..20 {
+            for b in -25..0 {
+                let material = &materials[rng.range_i32(0, materials.len() as i32) as usize];
+                let offset = Vec3::new(
+                    2.0 * a as f64 + rng.range_f64(0.0, 0.5),
+                    0.0,
+                    2.0 * b as f64 + rng.range_f64(0.0, 0.5),
+                );
+                let size = rng.range_f64(0.2, 0.35);
+                let m = Mat4::translation(&offset)
+                    * Mat4::rotation(&Vec3::new(0.0, 1.0, 0.0), rng.range_f64(0.0, 360.0))
+                    * Mat4::scaling(&Vec3::new(size, size, size));
+                instances.push(Instance::new(pot.clone(), m).with_material(material.clone()));
+            }
+        }
+        world.add(Arc::new(Instances::new(instances).unwrap()));
+
+        Scene {
+            world,
+            lookfrom: Point3::new(0.0, 12.0, 20.0),
+            lookat: Point3::new(0.0, 0.0, -15.0),
+            vfov: 40.0,
+            ..Default::default()
+        }
+    }
 }
@@ -0,0 +1,1176 @@
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use ron::extensions::Extensions;
+use serde::Deserialize;
+
+use super::Scene;
+use crate::algebra::Quat;
+use crate::background::{dark, sky, BackgroundPtr, Gradation, SolidBackground};
+use crate::hittable::{
+    load_obj, rotate_axis, rotate_x, rotate_y, rotate_z, scale, translate, AnimatedTransform,
+    BoxObj, BvhNode, ConstantMedium, HittableList, Instance, Instances, Keyframe, MeshData,
+    MovingSphere, Sphere, Triangle, TriangleMesh, XYRect, XZRect, YZRect,
+};
+use crate::material::{
+    Conductor, Dielectric, DiffuseLight, Isotropic, Lambertian, Metal, Principled, RoughDielectric,
+};
+use crate::texture::{Checker, ImageTexture, Marble, NoiseTexture, SolidColor, Turbulence};
+use crate::{Color, HittablePtr, MaterialPtr, Point3, Random, TexturePtr, Vec3};
+
+type Triple = [f64; 3];
+
+/// Per-axis scale factors, none of them zero.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "Triple")]
+pub struct ScaleFactor(Triple);
+
+impl TryFrom<Triple> for ScaleFactor {
+    type Error = &'static str;
+
+    fn try_from(factor: Triple) -> Result<Self, Self::Error> {
+        if factor.contains(&0.0) {
+            return Err("zero scale factor");
+        }
+        Ok(Self(factor))
+    }
+}
+
+/// Rotation axis, not the zero vector.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "Triple")]
+pub struct Axis(Triple);
+
+impl TryFrom<Triple> for Axis {
+    type Error = &'static str;
+
+    fn try_from(axis: Triple) -> Result<Self, Self::Error> {
+        if Vec3::from(axis).near_zero() {
+            return Err("zero rotation axis");
+        }
+        Ok(Self(axis))
+    }
+}
+
+/// Lists that must not be empty, checked while parsing so that the error
+/// carries a position.
+fn non_empty<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let v = Vec::deserialize(deserializer)?;
+    if v.is_empty() {
+        return Err(serde::de::Error::custom("expected at least one entry"));
+    }
+    Ok(v)
+}
+
+/// Top level of a `.ron` scene file.
+#[derive(Debug, Deserialize)]
+#[serde(rename = "Scene", deny_unknown_fields)]
+pub struct SceneFile {
+    #[serde(default)]
+    pub camera: CameraDesc,
+    #[serde(default)]
+    pub background: BackgroundDesc,
+    #[serde(default)]
+    pub textures: BTreeMap<String, TextureDesc>,
+    /// Built in name order, so that noise textures draw the same numbers
+    /// from the seeded rng on every run.
+    #[serde(default)]
+    pub materials: BTreeMap<String, MaterialDesc>,
+    #[serde(default)]
+    pub objects: Vec<ObjectDesc>,
+}
+
+/// Camera parameters. Missing fields fall back to `Scene::default()`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename = "Camera", deny_unknown_fields)]
+pub struct CameraDesc {
+    pub lookfrom: Option<Triple>,
+    pub lookat: Option<Triple>,
+    pub vup: Option<Triple>,
+    pub vfov: Option<f64>,
+    pub aperture: Option<f64>,
+    pub focus_dist: Option<f64>,
+    pub aspect_ratio: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub enum BackgroundDesc {
+    #[default]
+    Sky,
+    Dark,
+    Solid(Triple),
+    Gradation {
+        bottom: Triple,
+        top: Triple,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+pub enum TextureDesc {
+    Solid(Triple),
+    Checker {
+        even: Box<TextureDesc>,
+        odd: Box<TextureDesc>,
+    },
+    Image(String),
+    Noise {
+        scale: f64,
+    },
+    Turbulence {
+        scale: f64,
+    },
+    Marble {
+        scale: f64,
+    },
+    /// Reference to an entry of `textures`.
+    Named(String),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum MaterialDesc {
+    Lambertian {
+        albedo: TextureDesc,
+    },
+    Metal {
+        albedo: Triple,
+        fuzz: f64,
+    },
+    /// GGX metal; `roughness` goes from 0 (mirror) to 1.
+    Conductor {
+        fresnel: FresnelDesc,
+        roughness: f64,
+    },
+    Dielectric {
+        ir: f64,
+        /// Absorption per unit length inside, for each channel.
+        #[serde(default)]
+        absorption: Triple,
+    },
+    /// Frosted glass. `roughness_texture`, if given, replaces `roughness`
+    /// with the luminance of the texture.
+    RoughDielectric {
+        ir: f64,
+        #[serde(default)]
+        roughness: f64,
+        #[serde(default)]
+        roughness_texture: Option<TextureDesc>,
+    },
+    /// Uber material; see `Principled`. Each scalar parameter is a number
+    /// or, with the `_texture` field, the luminance of a texture. Unset
+    /// parameters keep their defaults.
+    Principled {
+        base_color: Option<Box<TextureDesc>>,
+        metallic: Option<f64>,
+        metallic_texture: Option<Box<TextureDesc>>,
+        roughness: Option<f64>,
+        roughness_texture: Option<Box<TextureDesc>>,
+        specular: Option<f64>,
+        specular_texture: Option<Box<TextureDesc>>,
+        clearcoat: Option<f64>,
+        clearcoat_texture: Option<Box<TextureDesc>>,
+        clearcoat_roughness: Option<f64>,
+        clearcoat_roughness_texture: Option<Box<TextureDesc>>,
+        sheen: Option<f64>,
+        sheen_texture: Option<Box<TextureDesc>>,
+        transmission: Option<f64>,
+        transmission_texture: Option<Box<TextureDesc>>,
+        ir: Option<f64>,
+        absorption: Option<Triple>,
+    },
+    DiffuseLight {
+        emit: TextureDesc,
+    },
+    Isotropic {
+        albedo: TextureDesc,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+pub enum FresnelDesc {
+    /// Reflectance at normal incidence.
+    Schlick(Triple),
+    /// Complex index of refraction `eta + i k` per channel.
+    Complex {
+        eta: Triple,
+        k: Triple,
+    },
+    Gold,
+    Copper,
+    Aluminium,
+    Silver,
+}
+
+/// Geometry. `material` fields name an entry of `materials`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum ObjectDesc {
+    Sphere {
+        center: Triple,
+        radius: f64,
+        material: String,
+    },
+    MovingSphere {
+        center0: Triple,
+        center1: Triple,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        material: String,
+    },
+    XYRect {
+        x0: f64,
+        x1: f64,
+        y0: f64,
+        y1: f64,
+        k: f64,
+        material: String,
+    },
+    XZRect {
+        x0: f64,
+        x1: f64,
+        z0: f64,
+        z1: f64,
+        k: f64,
+        material: String,
+    },
+    YZRect {
+        y0: f64,
+        y1: f64,
+        z0: f64,
+        z1: f64,
+        k: f64,
+        material: String,
+    },
+    Box {
+        p0: Triple,
+        p1: Triple,
+        material: String,
+    },
+    Triangle {
+        p0: Triple,
+        p1: Triple,
+        p2: Triple,
+        material: String,
+    },
+    /// OBJ, PLY or STL file, relative to the scene file. Without `material`,
+    /// OBJ materials come from the file's MTL libraries and other formats
+    /// are light gray, or vertex colored for PLY files with colors.
+    Mesh {
+        file: String,
+        material: Option<String>,
+    },
+    ConstantMedium {
+        boundary: Box<ObjectDesc>,
+        density: f64,
+        texture: TextureDesc,
+    },
+    Translate {
+        offset: Triple,
+        object: Box<ObjectDesc>,
+    },
+    RotateY {
+        angle: f64,
+        object: Box<ObjectDesc>,
+    },
+    RotateX {
+        angle: f64,
+        object: Box<ObjectDesc>,
+    },
+    RotateZ {
+        angle: f64,
+        object: Box<ObjectDesc>,
+    },
+    /// Counter-clockwise by `angle` degrees looking down `axis`.
+    Rotate {
+        axis: Axis,
+        angle: f64,
+        object: Box<ObjectDesc>,
+    },
+    /// Per-axis scale about the origin; factors must be nonzero.
+    Scale {
+        factor: ScaleFactor,
+        object: Box<ObjectDesc>,
+    },
+    /// Copies of `object` sharing its geometry, one per placement.
+    Instances {
+        object: Box<ObjectDesc>,
+        #[serde(deserialize_with = "non_empty")]
+        placements: Vec<PlacementDesc>,
+    },
+    /// Moves `object` through `keyframes` over the shutter interval.
+    Animated {
+        #[serde(deserialize_with = "non_empty")]
+        keyframes: Vec<KeyframeDesc>,
+        object: Box<ObjectDesc>,
+    },
+    #[serde(deserialize_with = "non_empty")]
+    Bvh(Vec<ObjectDesc>),
+    List(Vec<ObjectDesc>),
+}
+
+/// Placement of one of `Instances`: scaled, rotated by `angle` degrees
+/// about `axis`, then translated.
+#[derive(Debug, Deserialize)]
+#[serde(rename = "Place", deny_unknown_fields)]
+pub struct PlacementDesc {
+    pub translate: Option<Triple>,
+    pub axis: Option<Axis>,
+    pub angle: Option<f64>,
+    pub scale: Option<ScaleFactor>,
+}
+
+/// Pose of an `Animated` object at `time`: scaled, rotated by `angle`
+/// degrees about `axis`, then translated.
+#[derive(Debug, Deserialize)]
+#[serde(rename = "Keyframe", deny_unknown_fields)]
+pub struct KeyframeDesc {
+    pub time: f64,
+    pub translate: Option<Triple>,
+    pub axis: Option<Axis>,
+    pub angle: Option<f64>,
+    pub scale: Option<ScaleFactor>,
+}
+
+impl Scene {
+    pub fn from_file(path: impl AsRef<Path>, rng: &mut Random) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if let Some("gltf" | "glb") = path.extension().and_then(|e| e.to_str()) {
+            return Self::from_gltf(path);
+        }
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read scene file {}", path.display()))?;
+        Self::from_source(&source, path, rng)
+    }
+
+    fn from_source(source: &str, path: &Path, rng: &mut Random) -> anyhow::Result<Self> {
+        let file: SceneFile = ron::Options::default()
+            .with_default_extension(Extensions::IMPLICIT_SOME)
+            .from_str(source)
+            .map_err(|e| anyhow!("{}:{}", path.display(), e))?;
+        Builder::new(source, path, &file.textures, rng).build(&file)
+    }
+}
+
+struct Builder<'a> {
+    source: &'a str,
+    path: &'a Path,
+    base_dir: PathBuf,
+    texture_descs: &'a BTreeMap<String, TextureDesc>,
+    textures: HashMap<String, TexturePtr>,
+    resolving: Vec<String>,
+    materials: HashMap<String, MaterialPtr>,
+    rng: &'a mut Random,
+}
+
+impl<'a> Builder<'a> {
+    fn new(
+        source: &'a str,
+        path: &'a Path,
+        texture_descs: &'a BTreeMap<String, TextureDesc>,
+        rng: &'a mut Random,
+    ) -> Self {
+        let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Self {
+            source,
+            path,
+            base_dir,
+            texture_descs,
+            textures: HashMap::new(),
+            resolving: Vec::new(),
+            materials: HashMap::new(),
+            rng,
+        }
+    }
+
+    fn build(mut self, file: &SceneFile) -> anyhow::Result<Scene> {
+        for (name, desc) in &file.materials {
+            let material = self.material(desc)?;
+            self.materials.insert(name.clone(), material);
+        }
+
+        let mut world = HittableList::default();
+        let mut lights = HittableList::default();
+        for desc in &file.objects {
+            let object = self.object(desc)?;
+            if is_light(desc, &file.materials) {
+                lights.add(object.clone());
+            }
+            world.add(object);
+        }
+
+        let background: BackgroundPtr = match &file.background {
+            BackgroundDesc::Sky => sky(),
+            BackgroundDesc::Dark => dark(),
+            BackgroundDesc::Solid(c) => Box::new(SolidBackground::new(color(*c))),
+            BackgroundDesc::Gradation { bottom, top } => {
+                Box::new(Gradation::new(color(*bottom), color(*top)))
+            }
+        };
+
+        let default = Scene::default();
+        let camera = &file.camera;
+        Ok(Scene {
+            world,
+            lights,
+            background,
+            lookfrom: camera.lookfrom.map_or(default.lookfrom, Point3::from),
+            lookat: camera.lookat.map_or(default.lookat, Point3::from),
+            vup: camera.vup.map_or(default.vup, Vec3::from),
+            vfov: camera.vfov.unwrap_or(default.vfov),
+            aperture: camera.aperture.unwrap_or(default.aperture),
+            focus_dist: camera.focus_dist.unwrap_or(default.focus_dist),
+            aspect_ratio: camera.aspect_ratio.unwrap_or(default.aspect_ratio),
+        })
+    }
+
+    fn texture(&mut self, desc: &TextureDesc) -> anyhow::Result<TexturePtr> {
+        Ok(match desc {
+            TextureDesc::Solid(c) => Arc::new(SolidColor::new(color(*c))),
+            TextureDesc::Checker { even, odd } => {
+                Arc::new(Checker::new(self.texture(even)?, self.texture(odd)?))
+            }
+            TextureDesc::Image(file) => {
+                let texture = ImageTexture::new(self.base_dir.join(file)).map_err(|e| {
+                    self.error_at(
+                        file,
+                        Site::Use("Image("),
+                        format!("cannot load `{}`: {}", file, e),
+                    )
+                })?;
+                Arc::new(texture)
+            }
+            TextureDesc::Noise { scale } => Arc::new(NoiseTexture::with_rng(*scale, self.rng)),
+            TextureDesc::Turbulence { scale } => Arc::new(Turbulence::with_rng(*scale, self.rng)),
+            TextureDesc::Marble { scale } => Arc::new(Marble::with_rng(*scale, self.rng)),
+            TextureDesc::Named(name) => self.named_texture(name)?,
+        })
+    }
+
+    fn named_texture(&mut self, name: &str) -> anyhow::Result<TexturePtr> {
+        if let Some(texture) = self.textures.get(name) {
+            return Ok(texture.clone());
+        }
+        let desc = self.texture_descs.get(name).ok_or_else(|| {
+            self.error_at(
+                name,
+                Site::Use("Named("),
+                format!("unknown texture `{}`", name),
+            )
+        })?;
+        if self.resolving.iter().any(|n| n == name) {
+            return Err(self.error_at(
+                name,
+                Site::Definition("textures"),
+                format!("texture `{}` refers to itself", name),
+            ));
+        }
+        self.resolving.push(name.to_string());
+        let texture = self.texture(desc)?;
+        self.resolving.pop();
+        self.textures.insert(name.to_string(), texture.clone());
+        Ok(texture)
+    }
+
+    /// A scalar material parameter: the texture if there is one, else the
+    /// value if there is one, else `default`.
+    fn parameter(
+        &mut self,
+        value: Option<f64>,
+        texture: &Option<Box<TextureDesc>>,
+        default: TexturePtr,
+    ) -> anyhow::Result<TexturePtr> {
+        Ok(match (texture, value) {
+            (Some(desc), _) => self.texture(desc)?,
+            (None, Some(x)) => Principled::constant(x),
+            (None, None) => default,
+        })
+    }
+
+    fn material(&mut self, desc: &MaterialDesc) -> anyhow::Result<MaterialPtr> {
+        Ok(match desc {
+            MaterialDesc::Lambertian { albedo } => Arc::new(Lambertian::new(self.texture(albedo)?)),
+            MaterialDesc::Metal { albedo, fuzz } => Arc::new(Metal::new(color(*albedo), *fuzz)),
+            MaterialDesc::Conductor { fresnel, roughness } => Arc::new(match fresnel {
+                FresnelDesc::Schlick(f0) => Conductor::schlick(color(*f0), *roughness),
+                FresnelDesc::Complex { eta, k } => {
+                    Conductor::complex(color(*eta), color(*k), *roughness)
+                }
+                FresnelDesc::Gold => Conductor::gold(*roughness),
+                FresnelDesc::Copper => Conductor::copper(*roughness),
+                FresnelDesc::Aluminium => Conductor::aluminium(*roughness),
+                FresnelDesc::Silver => Conductor::silver(*roughness),
+            }),
+            MaterialDesc::Dielectric { ir, absorption } => {
+                Arc::new(Dielectric::with_absorption(*ir, color(*absorption)))
+            }
+            MaterialDesc::RoughDielectric {
+                ir,
+                roughness,
+                roughness_texture,
+            } => Arc::new(match roughness_texture {
+                Some(texture) => RoughDielectric::with_texture(*ir, self.texture(texture)?),
+                None => RoughDielectric::new(*ir, *roughness),
+            }),
+            MaterialDesc::Principled {
+                base_color,
+                metallic,
+                metallic_texture,
+                roughness,
+                roughness_texture,
+                specular,
+                specular_texture,
+                clearcoat,
+                clearcoat_texture,
+                clearcoat_roughness,
+                clearcoat_roughness_texture,
+                sheen,
+                sheen_texture,
+                transmission,
+                transmission_texture,
+                ir,
+                absorption,
+            } => {
+                let d = Principled::default();
+                Arc::new(Principled {
+                    base_color: match base_color {
+                        Some(desc) => self.texture(desc)?,
+                        None => d.base_color,
+                    },
+                    metallic: self.parameter(*metallic, metallic_texture, d.metallic)?,
+                    roughness: self.parameter(*roughness, roughness_texture, d.roughness)?,
+                    specular: self.parameter(*specular, specular_texture, d.specular)?,
+                    clearcoat: self.parameter(*clearcoat, clearcoat_texture, d.clearcoat)?,
+                    clearcoat_roughness: self.parameter(
+                        *clearcoat_roughness,
+                        clearcoat_roughness_texture,
+                        d.clearcoat_roughness,
+                    )?,
+                    sheen: self.parameter(*sheen, sheen_texture, d.sheen)?,
+                    transmission: self.parameter(
+                        *transmission,
+                        transmission_texture,
+                        d.transmission,
+                    )?,
+                    ir: ir.unwrap_or(d.ir),
+                    absorption: absorption.map_or(d.absorption, color),
+                })
+            }
+            MaterialDesc::DiffuseLight { emit } => Arc::new(DiffuseLight::new(self.texture(emit)?)),
+            MaterialDesc::Isotropic { albedo } => Arc::new(Isotropic::new(self.texture(albedo)?)),
+        })
+    }
+
+    fn named_material(&self, name: &str) -> anyhow::Result<MaterialPtr> {
+        self.materials.get(name).cloned().ok_or_else(|| {
+            self.error_at(
+                name,
+                Site::Use("material:"),
+                format!("unknown material `{}`", name),
+            )
+        })
+    }
+
+    fn object(&mut self, desc: &ObjectDesc) -> anyhow::Result<HittablePtr> {
+        Ok(match desc {
+            ObjectDesc::Sphere {
+                center,
+                radius,
+                material,
+            } => Arc::new(Sphere::new(
+                Point3::from(*center),
+                *radius,
+                self.named_material(material)?,
+            )),
+            ObjectDesc::MovingSphere {
+                center0,
+                center1,
+                time0,
+                time1,
+                radius,
+                material,
+            } => Arc::new(MovingSphere::new(
+                Point3::from(*center0),
+                Point3::from(*center1),
+                *time0,
+                *time1,
+                *radius,
+                self.named_material(material)?,
+            )),
+            ObjectDesc::XYRect {
+                x0,
+                x1,
+                y0,
+                y1,
+                k,
+                material,
+            } => Arc::new(XYRect::new(
+                *x0,
+                *x1,
+                *y0,
+                *y1,
+                *k,
+                self.named_material(material)?,
+            )),
+            ObjectDesc::XZRect {
+                x0,
+                x1,
+                z0,
+                z1,
+                k,
+                material,
+            } => Arc::new(XZRect::new(
+                *x0,
+                *x1,
+                *z0,
+                *z1,
+                *k,
+                self.named_material(material)?,
+            )),
+            ObjectDesc::YZRect {
+                y0,
+                y1,
+                z0,
+                z1,
+                k,
+                material,
+            } => Arc::new(YZRect::new(
+                *y0,
+                *y1,
+                *z0,
+                *z1,
+                *k,
+                self.named_material(material)?,
+            )),
+            ObjectDesc::Box { p0, p1, material } => Arc::new(BoxObj::new(
+                Point3::from(*p0),
+                Point3::from(*p1),
+                self.named_material(material)?,
+            )),
+            ObjectDesc::Triangle {
+                p0,
+                p1,
+                p2,
+                material,
+            } => Arc::new(Triangle::new(
+                Point3::from(*p0),
+                Point3::from(*p1),
+                Point3::from(*p2),
+                self.named_material(material)?,
+            )),
+            ObjectDesc::Mesh { file, material } => {
+                let path = self.base_dir.join(file);
+                let is_obj = match path.extension() {
+                    Some(e) => e.eq_ignore_ascii_case("obj"),
+                    None => true,
+                };
+                let mesh: anyhow::Result<HittablePtr> = match material {
+                    Some(material) => {
+                        let material = self.named_material(material)?;
+                        TriangleMesh::load(path, material).map(|m| Arc::new(m) as HittablePtr)
+                    }
+                    None if is_obj => load_obj(path, gray()).map(|m| Arc::new(m) as HittablePtr),
+                    None => MeshData::load(path).and_then(|data| {
+                        let mesh = if data.colors.is_empty() {
+                            TriangleMesh::new(data, gray())
+                        } else {
+                            TriangleMesh::with_vertex_colors(data)
+                        };
+                        Ok(Arc::new(mesh.context("No triangles")?) as HittablePtr)
+                    }),
+                };
+                mesh.map_err(|e| {
+                    self.error_at(
+                        file,
+                        Site::Use("file:"),
+                        format!("cannot load `{}`: {}", file, e),
+                    )
+                })?
+            }
+            ObjectDesc::ConstantMedium {
+                boundary,
+                density,
+                texture,
+            } => Arc::new(ConstantMedium::new(
+                self.object(boundary)?,
+                *density,
+                self.texture(texture)?,
+            )),
+            ObjectDesc::Translate { offset, object } => {
+                translate(self.object(object)?, Vec3::from(*offset))
+            }
+            ObjectDesc::RotateY { angle, object } => rotate_y(self.object(object)?, *angle),
+            ObjectDesc::RotateX { angle, object } => rotate_x(self.object(object)?, *angle),
+            ObjectDesc::RotateZ { angle, object } => rotate_z(self.object(object)?, *angle),
+            ObjectDesc::Rotate {
+                axis,
+                angle,
+                object,
+            } => rotate_axis(self.object(object)?, &Vec3::from(axis.0), *angle),
+            ObjectDesc::Scale { factor, object } => {
+                scale(self.object(object)?, &Vec3::from(factor.0))
+            }
+            ObjectDesc::Instances { object, placements } => {
+                let geometry = self.object(object)?;
+                let instances = placements
+                    .iter()
+                    .map(|p| {
+                        let pose = pose(0.0, p.translate, p.axis, p.angle, p.scale);
+                        Instance::new(geometry.clone(), pose.matrix())
+                    })
+                    .collect();
+                Arc::new(Instances::new(instances).context(format!(
+                    "{}: Instances of an unbounded object",
+                    self.path.display()
+                ))?)
+            }
+            ObjectDesc::Animated { keyframes, object } => {
+                let keyframes = keyframes.iter().map(keyframe).collect();
+                Arc::new(AnimatedTransform::new(self.object(object)?, keyframes))
+            }
+            ObjectDesc::Bvh(descs) => {
+                let objects = descs
+                    .iter()
+                    .map(|d| self.object(d))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                Arc::new(
+                    BvhNode::new(&objects, 0.0, 1.0)
+                        .context(format!("{}: unbounded object in Bvh", self.path.display()))?,
+                )
+            }
+            ObjectDesc::List(descs) => {
+                let mut list = HittableList::default();
+                for d in descs {
+                    list.add(self.object(d)?);
+                }
+                Arc::new(list)
+            }
+        })
+    }
+
+    /// Reports `msg` at the first place `name` is quoted at `site` in the
+    /// source.
+    fn error_at(&self, name: &str, site: Site, msg: impl Display) -> anyhow::Error {
+        match locate(self.source, name, site) {
+            Some((line, col)) => anyhow!("{}:{}:{}: {}", self.path.display(), line, col, msg),
+            None => anyhow!("{}: {}", self.path.display(), msg),
+        }
+    }
+}
+
+/// Keyframe of an `Animated` object.
+fn keyframe(desc: &KeyframeDesc) -> Keyframe {
+    let KeyframeDesc {
+        time,
+        translate,
+        axis,
+        angle,
+        scale,
+    } = *desc;
+    pose(time, translate, axis, angle, scale)
+}
+
+/// Pose from the fields of a `KeyframeDesc`, which `Instances` placements
+/// share. Rotations default to the y axis.
+fn pose(
+    time: f64,
+    translate: Option<Triple>,
+    axis: Option<Axis>,
+    angle: Option<f64>,
+    scale: Option<ScaleFactor>,
+) -> Keyframe {
+    let rotation = match angle {
+        None => Quat::identity(),
+        Some(angle) => {
+            let axis = axis.map_or([0.0, 1.0, 0.0], |a| a.0);
+            Quat::from_axis_angle(&Vec3::from(axis), angle)
+        }
+    };
+    Keyframe::new(
+        time,
+        Vec3::from(translate.unwrap_or_default()),
+        rotation,
+        Vec3::from(scale.map_or([1.0, 1.0, 1.0], |s| s.0)),
+    )
+}
+
+/// Whether `desc` is an emitter the integrator can sample directly.
+fn is_light(desc: &ObjectDesc, materials: &BTreeMap<String, MaterialDesc>) -> bool {
+    let material = match desc {
+        ObjectDesc::Sphere { material, .. }
+        | ObjectDesc::XYRect { material, .. }
+        | ObjectDesc::XZRect { material, .. }
+        | ObjectDesc::YZRect { material, .. } => material,
+        // `RotateY` cannot sample the object it wraps.
+        ObjectDesc::Translate { object, .. }
+        | ObjectDesc::RotateX { object, .. }
+        | ObjectDesc::RotateZ { object, .. }
+        | ObjectDesc::Rotate { object, .. }
+        | ObjectDesc::Scale { object, .. } => return is_light(object, materials),
+        _ => return false,
+    };
+    matches!(
+        materials.get(material),
+        Some(MaterialDesc::DiffuseLight { .. })
+    )
+}
+
+/// Material of meshes that come without one.
+fn gray() -> MaterialPtr {
+    Arc::new(Lambertian::with_color(Color::new(0.73, 0.73, 0.73)))
+}
+
+fn color(c: Triple) -> Color {
+    Color(Vec3::from(c))
+}
+
+/// Where a name that an error is about appears in the source.
+#[derive(Debug, Clone, Copy)]
+enum Site<'a> {
+    /// As a key of the `textures` or `materials` map.
+    Definition(&'a str),
+    /// Right after `prefix`, e.g. `material:` or `Named(`.
+    Use(&'a str),
+}
+
+/// 1-based line and column of the first place `name` is quoted at `site`.
+fn locate(source: &str, name: &str, site: Site) -> Option<(usize, usize)> {
+    let quoted = format!("\"{}\"", name);
+    let section = match site {
+        Site::Definition(section) => section_span(source, section)?,
+        Site::Use(_) => 0..source.len(),
+    };
+    let (offset, _) = source.match_indices(&quoted).find(|&(i, _)| {
+        section.contains(&i)
+            && match site {
+                Site::Definition(_) => source[i + quoted.len()..].trim_start().starts_with(':'),
+                Site::Use(prefix) => source[..i].trim_end().ends_with(prefix),
+            }
+    })?;
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let col = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    Some((line, col))
+}
+
+/// Byte range of the braces after the top-level field `field:`.
+fn section_span(source: &str, field: &str) -> Option<std::ops::Range<usize>> {
+    let start = source.match_indices(field).find_map(|(i, _)| {
+        let rest = source[i + field.len()..].trim_start().strip_prefix(':')?;
+        let rest = rest.trim_start();
+        rest.starts_with('{').then(|| source.len() - rest.len())
+    })?;
+    let (mut depth, mut in_string) = (0, false);
+    for (i, c) in source[start..].char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '{' if !in_string => depth += 1,
+            '}' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(start..start + i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Hittable, Ray, RenderSettings, Renderer};
+
+    fn load(source: &str) -> anyhow::Result<Scene> {
+        Scene::from_source(source, Path::new("test.ron"), &mut Random::default())
+    }
+
+    #[test]
+    fn load_bundled_scenes() -> anyhow::Result<()> {
+        for path in &[
+            "scenes/cornell_box.ron",
+            "scenes/teapot.ron",
+            "scenes/cubes.gltf",
+            "scenes/motion_blur.ron",
+            "scenes/metals.ron",
+            "scenes/frosted_glass.ron",
+            "scenes/colored_glass.ron",
+            "scenes/principled.ron",
+        ] {
+            Scene::from_file(path, &mut Random::default())?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn collects_lights() -> anyhow::Result<()> {
+        let scene = Scene::from_file("scenes/cornell_box.ron", &mut Random::default())?;
+        assert!(!scene.lights.is_empty());
+        let scene = Scene::from_file("scenes/teapot.ron", &mut Random::default())?;
+        assert!(scene.lights.is_empty());
+
+        // Lights inside transforms are sampled through them.
+        for object in [
+            r#"RotateX(angle: 30, object: Scale(
+      factor: (2, 1, 1),
+      object: XZRect(x0: 0, x1: 1, z0: 0, z1: 1, k: 2, material: "lamp"),
+    ))"#,
+            r#"Rotate(axis: (1, 1, 0), angle: 45, object: RotateZ(
+      angle: 10,
+      object: Sphere(center: (0, 5, 0), radius: 1, material: "lamp"),
+    ))"#,
+        ] {
+            let source = format!(
+                r#"(
+  materials: {{"lamp": DiffuseLight(emit: Solid((4, 4, 4)))}},
+  objects: [
+    {},
+  ],
+)"#,
+                object
+            );
+            let scene = load(&source)?;
+            assert!(!scene.lights.is_empty(), "{}", object);
+            let (o, mut rng) = (Point3::new(0.0, -3.0, 0.0), Random::new(0));
+            let v = scene.lights.random(&o, &mut rng);
+            assert!(scene.lights.pdf_value(&o, &v, &mut rng) > 0.0, "{}", object);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_object_type() {
+        let source = r#"(
+  objects: [
+    Spere(center: (0, 0, 0), radius: 1, material: "m"),
+  ],
+)"#;
+        let err = load(source).err().unwrap();
+        assert!(err.to_string().starts_with("test.ron:3:"), "{}", err);
+    }
+
+    #[test]
+    fn bad_parameter() {
+        let source = r#"(
+  camera: (
+    vfov: "wide",
+  ),
+)"#;
+        let err = load(source).err().unwrap();
+        assert!(err.to_string().starts_with("test.ron:3:"), "{}", err);
+    }
+
+    #[test]
+    fn unknown_material() {
+        let source = r#"(
+  materials: {
+    "white": Lambertian(albedo: Solid((0.73, 0.73, 0.73))),
+  },
+  objects: [
+    Sphere(center: (0, 0, 0), radius: 1, material: "white"),
+    Sphere(center: (0, 2, 0), radius: 1, material: "black"),
+  ],
+)"#;
+        let err = load(source).err().unwrap();
+        assert_eq!(err.to_string(), "test.ron:7:52: unknown material `black`");
+    }
+
+    #[test]
+    fn texture_cycle() {
+        let source = r#"(
+  textures: {
+    "a": Checker(even: Named("b"), odd: Solid((0, 0, 0))),
+    "b": Checker(even: Named("a"), odd: Solid((0, 0, 0))),
+  },
+  materials: {
+    "m": Lambertian(albedo: Named("a")),
+  },
+)"#;
+        // At the definition, not at the first mention of the name.
+        assert_eq!(
+            load(source).err().unwrap().to_string(),
+            "test.ron:3:5: texture `a` refers to itself"
+        );
+    }
+
+    #[test]
+    fn errors_locate_their_object() {
+        let cases = [
+            (
+                r#"Rotate(axis: (0, 0, 0), angle: 5, object: List([]))"#,
+                "test.ron:5:27: zero rotation axis",
+            ),
+            (
+                r#"Animated(keyframes: [], object: List([]))"#,
+                "test.ron:5:27: expected at least one entry",
+            ),
+            (
+                "Instances(object: List([]), placements: [])",
+                "test.ron:5:47: expected at least one entry",
+            ),
+            ("Bvh([])", "test.ron:5:11: expected at least one entry"),
+            (
+                r#"Sphere(center: (0, 0, 0), radius: 1, material: "s")"#,
+                "test.ron:5:52: unknown material `s`",
+            ),
+        ];
+        for (object, expected) in cases {
+            // `s` also names a texture, used before the object refers to it.
+            let source = format!(
+                r#"(
+  textures: {{"s": Solid((1, 1, 1))}},
+  materials: {{"m": Lambertian(albedo: Named("s"))}},
+  objects: [
+    {},
+  ],
+)"#,
+                object
+            );
+            assert_eq!(load(&source).err().unwrap().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn general_transforms() -> anyhow::Result<()> {
+        let source = r#"(
+  materials: {
+    "white": Lambertian(albedo: Solid((0.73, 0.73, 0.73))),
+  },
+  objects: [
+    RotateX(angle: 90, object: Scale(
+      factor: (1, 1, 2),
+      object: Sphere(center: (0, 0, 0), radius: 1, material: "white"),
+    )),
+  ],
+)"#;
+        let scene = load(source)?;
+        let bb = scene.world.bounding_box(0.0, 1.0).unwrap();
+        assert!((bb.minimum.y + 2.0).abs() < 1e-9 && (bb.maximum.y - 2.0).abs() < 1e-9);
+
+        let source = source.replace("(1, 1, 2)", "(1, 0, 2)");
+        assert_eq!(
+            load(&source).err().unwrap().to_string(),
+            "test.ron:7:24: zero scale factor"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn instances() -> anyhow::Result<()> {
+        let source = r#"(
+  materials: {
+    "white": Lambertian(albedo: Solid((0.73, 0.73, 0.73))),
+  },
+  objects: [
+    Instances(
+      object: Sphere(center: (0, 0, 0), radius: 1, material: "white"),
+      placements: [
+        Place(translate: (-5, 0, 0)),
+        Place(translate: (5, 0, 0), scale: (2, 2, 2)),
+      ],
+    ),
+  ],
+)"#;
+        let bb = load(source)?.world.bounding_box(0.0, 1.0).unwrap();
+        assert_eq!(bb.minimum, Point3::new(-6.0, -2.0, -2.0));
+        assert_eq!(bb.maximum, Point3::new(7.0, 2.0, 2.0));
+        Ok(())
+    }
+
+    #[test]
+    fn noise_is_reproducible() -> anyhow::Result<()> {
+        let source = r#"(
+  camera: (lookfrom: (0, 0, 12), lookat: (0, 0, 0)),
+  materials: {
+    "a": Lambertian(albedo: Marble(scale: 4)),
+    "b": Lambertian(albedo: Noise(scale: 2)),
+    "c": Lambertian(albedo: Turbulence(scale: 3)),
+    "d": Lambertian(albedo: Marble(scale: 1)),
+    "e": Lambertian(albedo: Noise(scale: 5)),
+    "f": Lambertian(albedo: Turbulence(scale: 7)),
+  },
+  objects: [
+    Sphere(center: (-5, 0, 0), radius: 1, material: "a"),
+    Sphere(center: (-3, 0, 0), radius: 1, material: "b"),
+    Sphere(center: (-1, 0, 0), radius: 1, material: "c"),
+    Sphere(center: (1, 0, 0), radius: 1, material: "d"),
+    Sphere(center: (3, 0, 0), radius: 1, material: "e"),
+    Sphere(center: (5, 0, 0), radius: 1, material: "f"),
+  ],
+)"#;
+        let render = || -> anyhow::Result<_> {
+            let scene = Scene::from_source(source, Path::new("test.ron"), &mut Random::new(42))?;
+            let settings = RenderSettings {
+                image_width: 40,
+                image_height: 10,
+                samples_per_pixel: 1,
+                max_depth: 2,
+                seed: 42,
+                ..Default::default()
+            };
+            Ok(Renderer::new(&scene, settings).render())
+        };
+        let first = render()?;
+        for _ in 0..3 {
+            assert_eq!(render()?, first);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn vertex_colors() -> anyhow::Result<()> {
+        let dir =
+            std::env::temp_dir().join(format!("raytracing-{}-vertex-colors", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(
+            dir.join("triangle.ply"),
+            "ply\nformat ascii 1.0\nelement vertex 3\n\
+             property float x\nproperty float y\nproperty float z\n\
+             property uchar red\nproperty uchar green\nproperty uchar blue\n\
+             element face 1\nproperty list uchar int vertex_indices\nend_header\n\
+             0 0 0 255 0 0\n3 0 0 0 255 0\n0 3 0 0 0 255\n3 0 1 2\n",
+        )?;
+        let albedo = |x: f64, y: f64, material: &str| -> anyhow::Result<Color> {
+            let source = format!(
+                r#"(
+  materials: {{"blue": Lambertian(albedo: Solid((0, 0, 0.5)))}},
+  objects: [Mesh(file: "triangle.ply"{})],
+)"#,
+                material
+            );
+            let mut rng = Random::new(0);
+            let scene = Scene::from_source(&source, &dir.join("test.ron"), &mut rng)?;
+            let r = Ray::new(Point3::new(x, y, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+            let rec = scene.world.hit(&r, 0.001, f64::INFINITY, &mut rng).unwrap();
+            let along_normal = Ray::new(rec.p.clone(), rec.normal.clone(), 0.0);
+            Ok(rec.mat_ptr.eval(&r, &rec, &along_normal) * std::f64::consts::PI)
+        };
+        // Barycentric weights of the red, green and blue corners.
+        for (x, y, expected) in [
+            (1.0, 1.0, [1.0 / 3.0; 3]),
+            (2.0, 0.5, [1.0 / 6.0, 2.0 / 3.0, 1.0 / 6.0]),
+        ] {
+            let colored = albedo(x, y, "")?;
+            assert!(
+                (0..3).all(|k| (colored.0[k] - expected[k]).abs() < 1e-9),
+                "{:?}",
+                colored
+            );
+        }
+        assert_eq!(
+            albedo(1.0, 1.0, r#", material: "blue""#)?,
+            Color::new(0.0, 0.0, 0.5)
+        );
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,430 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context};
+use gltf::camera::Projection;
+use gltf::image::Format;
+use gltf::khr_lights_punctual::{Kind, Light};
+use gltf::mesh::Mode;
+use image::RgbImage;
+
+use super::Scene;
+use crate::algebra::Mat4;
+use crate::background::{dark, sky};
+use crate::hittable::{BvhNode, Hittable, HittableList, MeshData, Sphere, TriangleMesh};
+use crate::material::{Dielectric, DiffuseLight, Principled};
+use crate::texture::{Channel, ImageTexture, Product, SolidColor};
+use crate::{Color, HittablePtr, MaterialPtr, Point3, TexturePtr, Vec3};
+
+/// Point and spot lights become emissive spheres of this radius, in scene
+/// units (metres for glTF).
+const POINT_LIGHT_RADIUS: f64 = 0.05;
+
+impl Scene {
+    /// Imports the default scene of a glTF or GLB file with its buffers and
+    /// textures, embedded or external.
+    ///
+    /// Meshes are flattened into world space, one `TriangleMesh` per
+    /// primitive. The first perspective camera becomes the scene camera;
+    /// without one the camera frames the whole scene. Point and spot lights
+    /// are approximated by small emissive spheres, directional lights are
+    /// not supported.
+    pub fn from_gltf(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let (doc, buffers, images) = gltf::import(path)
+            .with_context(|| format!("Cannot read glTF file {}", path.display()))?;
+        Importer::new(&buffers, &images)
+            .import(&doc)
+            .with_context(|| format!("{}", path.display()))
+    }
+}
+
+struct Camera {
+    lookfrom: Point3,
+    lookat: Point3,
+    vup: Vec3,
+    vfov: f64,
+    aspect_ratio: Option<f64>,
+}
+
+struct Importer<'a> {
+    buffers: &'a [gltf::buffer::Data],
+    images: &'a [gltf::image::Data],
+    textures: HashMap<usize, TexturePtr>,
+    materials: HashMap<Option<usize>, MaterialPtr>,
+    objects: Vec<HittablePtr>,
+    lights: HittableList,
+    emissive: bool,
+    camera: Option<Camera>,
+}
+
+impl<'a> Importer<'a> {
+    fn new(buffers: &'a [gltf::buffer::Data], images: &'a [gltf::image::Data]) -> Self {
+        Self {
+            buffers,
+            images,
+            textures: HashMap::new(),
+            materials: HashMap::new(),
+            objects: Vec::new(),
+            lights: HittableList::default(),
+            emissive: false,
+            camera: None,
+        }
+    }
+
+    fn import(mut self, doc: &gltf::Document) -> anyhow::Result<Scene> {
+        let scene = doc
+            .default_scene()
+            .or_else(|| doc.scenes().next())
+            .context("no scene")?;
+        for node in scene.nodes() {
+            self.node(&node, &Mat4::identity())?;
+        }
+
+        let mut world = HittableList::default();
+        let bvh = BvhNode::new(&self.objects, 0.0, 1.0).context("no geometry")?;
+        let bb = bvh.bounding_box(0.0, 1.0).context("no geometry")?;
+        world.add(Arc::new(bvh));
+
+        let default = Scene::default();
+        let camera = self.camera.unwrap_or_else(|| {
+            // Look at the scene's bounding sphere from the front.
+            let center = bb.centroid();
+            let radius = (&bb.maximum - &bb.minimum).length() / 2.0;
+            let distance = radius / (default.vfov.to_radians() / 2.0).sin();
+            Camera {
+                lookfrom: &center + Vec3::new(0.0, 0.0, distance),
+                lookat: center,
+                vup: Vec3::new(0.0, 1.0, 0.0),
+                vfov: default.vfov,
+                aspect_ratio: None,
+            }
+        });
+        let background = if self.emissive || !self.lights.is_empty() {
+            dark()
+        } else {
+            sky()
+        };
+        Ok(Scene {
+            world,
+            lights: self.lights,
+            background,
+            lookfrom: camera.lookfrom,
+            lookat: camera.lookat,
+            vup: camera.vup,
+            vfov: camera.vfov,
+            aspect_ratio: camera.aspect_ratio.unwrap_or(default.aspect_ratio),
+            ..default
+        })
+    }
+
+    fn node(&mut self, node: &gltf::Node, parent: &Mat4) -> anyhow::Result<()> {
+        let cols = node.transform().matrix().map(|c| c.map(|x| x as f64));
+        let m = parent * Mat4::from_cols(cols);
+
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                self.primitive(&primitive, &m)
+                    .with_context(|| format!("mesh `{}`", mesh.name().unwrap_or("")))?;
+            }
+        }
+        if let Some(camera) = node.camera() {
+            if let (None, Projection::Perspective(p)) = (&self.camera, camera.projection()) {
+                let lookfrom = m.transform_point(&Point3::default());
+                let forward = m.transform_vector(&Vec3::new(0.0, 0.0, -1.0));
+                self.camera = Some(Camera {
+                    lookat: &lookfrom + forward,
+                    lookfrom,
+                    vup: m.transform_vector(&Vec3::new(0.0, 1.0, 0.0)),
+                    vfov: (p.yfov() as f64).to_degrees(),
+                    aspect_ratio: p.aspect_ratio().map(|a| a as f64),
+                });
+            }
+        }
+        if let Some(light) = node.light() {
+            self.light(&light, &m);
+        }
+        for child in node.children() {
+            self.node(&child, &m)?;
+        }
+        Ok(())
+    }
+
+    fn primitive(&mut self, primitive: &gltf::Primitive, m: &Mat4) -> anyhow::Result<()> {
+        if primitive.mode() != Mode::Triangles {
+            bail!("unsupported primitive mode {:?}", primitive.mode());
+        }
+        let buffers = self.buffers;
+        let reader = primitive.reader(|b| Some(&buffers[b.index()]));
+
+        let positions: Vec<Point3> = reader
+            .read_positions()
+            .context("primitive without positions")?
+            .map(|p| m.transform_point(&Vec3::from(p)))
+            .collect();
+        let indices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        // A mirroring transform flips the winding, and with it the
+        // geometric normal.
+        let mirrored = m.det3() < 0.0;
+        let mut data = MeshData {
+            positions,
+            indices: indices
+                .chunks_exact(3)
+                .map(|t| {
+                    if mirrored {
+                        [t[0], t[2], t[1]]
+                    } else {
+                        [t[0], t[1], t[2]]
+                    }
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        if let Some(uvs) = reader.read_tex_coords(0) {
+            // glTF puts v = 0 at the top of the image.
+            data.uvs = uvs
+                .into_f32()
+                .map(|[u, v]| [u as f64, 1.0 - v as f64])
+                .collect();
+            data.uv_indices = Some(data.indices.clone());
+        }
+        match reader.read_normals() {
+            Some(normals) => {
+                let normal_m = m.inverse().context("singular node transform")?.transpose();
+                data.normals = normals
+                    .map(|n| normal_m.transform_vector(&Vec3::from(n)).unit_vector())
+                    .collect();
+                data.normal_indices = Some(data.indices.clone());
+            }
+            None => data.generate_normals(),
+        }
+
+        let material = self.material(&primitive.material())?;
+        if let Some(mesh) = TriangleMesh::new(data, material) {
+            self.objects.push(Arc::new(mesh));
+        }
+        Ok(())
+    }
+
+    /// Maps emissive materials to `DiffuseLight` and all others to
+    /// `Principled`.
+    fn material(&mut self, material: &gltf::Material) -> anyhow::Result<MaterialPtr> {
+        if let Some(m) = self.materials.get(&material.index()) {
+            return Ok(m.clone());
+        }
+
+        let strength = material.emissive_strength().unwrap_or(1.0) as f64;
+        let [r, g, b] = material.emissive_factor().map(|x| x as f64);
+        let emit = Color::new(r, g, b) * strength;
+
+        let m: MaterialPtr = if emit.luminance() > 0.0 {
+            self.emissive = true;
+            let mut texture: TexturePtr = Arc::new(SolidColor::new(emit));
+            if let Some(info) = material.emissive_texture() {
+                texture = Arc::new(Product::new(self.texture(&info.texture())?, texture));
+            }
+            Arc::new(DiffuseLight::new(texture))
+        } else {
+            Arc::new(self.principled(material)?)
+        };
+        self.materials.insert(material.index(), m.clone());
+        Ok(m)
+    }
+
+    /// The base color, metallic and roughness factors times their textures,
+    /// the transmission factor, the index of refraction and the absorption
+    /// of a volume. Blended materials transmit what their alpha does not
+    /// cover.
+    fn principled(&mut self, material: &gltf::Material) -> anyhow::Result<Principled> {
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, alpha] = pbr.base_color_factor().map(|x| x as f64);
+        let mut base_color: TexturePtr = Arc::new(SolidColor::new(Color::new(r, g, b)));
+        if let Some(info) = pbr.base_color_texture() {
+            base_color = Arc::new(Product::new(self.texture(&info.texture())?, base_color));
+        }
+        let mut metallic = Principled::constant(pbr.metallic_factor() as f64);
+        let mut roughness = Principled::constant(pbr.roughness_factor() as f64);
+        if let Some(info) = pbr.metallic_roughness_texture() {
+            // Roughness is packed in green and metalness in blue.
+            let packed = self.texture(&info.texture())?;
+            let channel = |c| Arc::new(Channel::new(packed.clone(), c));
+            metallic = Arc::new(Product::new(channel(2), metallic));
+            roughness = Arc::new(Product::new(channel(1), roughness));
+        }
+
+        let mut transmission = material
+            .transmission()
+            .map_or(0.0, |t| t.transmission_factor() as f64);
+        if material.alpha_mode() == gltf::material::AlphaMode::Blend {
+            transmission = transmission.max(1.0 - alpha);
+        }
+        let absorption = match material.volume() {
+            // Thin-walled surfaces have no inside to absorb in.
+            Some(volume)
+                if volume.thickness_factor() > 0.0 && volume.attenuation_distance().is_finite() =>
+            {
+                let [r, g, b] = volume.attenuation_color().map(|x| x as f64);
+                let distance = volume.attenuation_distance() as f64;
+                Dielectric::absorption_for(Color::new(r, g, b), distance)
+            }
+            _ => Color::default(),
+        };
+        Ok(Principled {
+            base_color,
+            metallic,
+            roughness,
+            transmission: Principled::constant(transmission),
+            ir: material.ior().unwrap_or(1.5) as f64,
+            absorption,
+            ..Default::default()
+        })
+    }
+
+    fn texture(&mut self, texture: &gltf::Texture) -> anyhow::Result<TexturePtr> {
+        let index = texture.source().index();
+        if let Some(t) = self.textures.get(&index) {
+            return Ok(t.clone());
+        }
+        let data = &self.images[index];
+        let t: TexturePtr = Arc::new(ImageTexture::from_image(to_rgb_image(data)?));
+        self.textures.insert(index, t.clone());
+        Ok(t)
+    }
+
+    fn light(&mut self, light: &Light, m: &Mat4) {
+        match light.kind() {
+            Kind::Point | Kind::Spot { .. } => {
+                let [r, g, b] = light.color().map(|x| x as f64);
+                // A sphere of radius R with radiance L has intensity
+                // L * pi * R^2 in every direction.
+                let radiance = light.intensity() as f64 / (PI * POINT_LIGHT_RADIUS.powi(2));
+                let material = Arc::new(DiffuseLight::with_color(Color::new(r, g, b) * radiance));
+                let center = m.transform_point(&Point3::default());
+                let sphere: HittablePtr =
+                    Arc::new(Sphere::new(center, POINT_LIGHT_RADIUS, material));
+                self.objects.push(sphere.clone());
+                self.lights.add(sphere);
+            }
+            Kind::Directional => {}
+        }
+    }
+}
+
+/// Keeps the most significant byte of each of the first three channels.
+fn to_rgb_image(data: &gltf::image::Data) -> anyhow::Result<RgbImage> {
+    let (channels, bytes) = match data.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (2, 1),
+        Format::R8G8B8 => (3, 1),
+        Format::R8G8B8A8 => (4, 1),
+        Format::R16 => (1, 2),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (3, 2),
+        Format::R16G16B16A16 => (4, 2),
+        format => bail!("unsupported texture format {:?}", format),
+    };
+    let pixels = data
+        .pixels
+        .chunks_exact(channels * bytes)
+        .flat_map(|px| {
+            // Little-endian, so the high byte comes last.
+            let channel = |c: usize| px[c.min(channels - 1) * bytes + bytes - 1];
+            match channels {
+                1 | 2 => [channel(0); 3],
+                _ => [channel(0), channel(1), channel(2)],
+            }
+        })
+        .collect();
+    RgbImage::from_raw(data.width, data.height, pixels)
+        .ok_or_else(|| anyhow!("texture data does not match its size"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Random, Ray};
+
+    #[test]
+    fn imports_node_hierarchy() -> anyhow::Result<()> {
+        let scene = Scene::from_gltf("scenes/cubes.gltf")?;
+        assert!((&scene.lookfrom - Point3::new(0.0, 2.2, 6.0)).length() < 1e-6);
+        assert!((scene.vfov - 40.0).abs() < 1e-4);
+        assert!(!scene.lights.is_empty());
+
+        // The top cube sits on its parent: 0.5 + 0.85 + 0.7 / 2 above the floor.
+        let down = Ray::new(Point3::new(0.0, 10.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let rec = scene
+            .world
+            .hit(&down, 0.001, f64::INFINITY, &mut Random::default())
+            .unwrap();
+        assert!((rec.p.y - 1.7).abs() < 1e-6, "{:?}", rec.p);
+        assert!((rec.normal.y - 1.0).abs() < 1e-6);
+        Ok(())
+    }
+
+    #[test]
+    fn metallic_roughness_texture() -> anyhow::Result<()> {
+        let json = r#"{
+  "asset": {"version": "2.0"},
+  "images": [{"uri": "base.png"}, {"uri": "metal_rough.png"}],
+  "textures": [{"source": 0}, {"source": 1}],
+  "materials": [{
+    "pbrMetallicRoughness": {
+      "baseColorFactor": [0.5, 1.0, 1.0, 1.0],
+      "baseColorTexture": {"index": 0},
+      "metallicFactor": 0.5,
+      "metallicRoughnessTexture": {"index": 1}
+    }
+  }]
+}"#;
+        let doc = gltf::Gltf::from_slice(json.as_bytes())?.document;
+        let pixel = |rgb: [u8; 3]| gltf::image::Data {
+            pixels: rgb.to_vec(),
+            format: Format::R8G8B8,
+            width: 1,
+            height: 1,
+        };
+        let images = [pixel([255, 255, 0]), pixel([0, 51, 255])];
+        let mut importer = Importer::new(&[], &images);
+        let m = importer.principled(&doc.materials().next().unwrap())?;
+
+        let value = |t: &TexturePtr| t.value(0.5, 0.5, &Point3::default());
+        assert_eq!(value(&m.base_color), Color::new(0.5, 1.0, 0.0));
+        assert_eq!(value(&m.metallic), Color::new(0.5, 0.5, 0.5));
+        let roughness = value(&m.roughness).0;
+        assert!((roughness.x - 0.2).abs() < 1e-12 && roughness.x == roughness.z);
+        Ok(())
+    }
+    #[test]
+    fn emissive_texture_is_scaled() -> anyhow::Result<()> {
+        let json = r#"{
+  "asset": {"version": "2.0"},
+  "extensionsUsed": ["KHR_materials_emissive_strength"],
+  "images": [{"uri": "glow.png"}],
+  "textures": [{"source": 0}],
+  "materials": [{
+    "emissiveFactor": [1.0, 0.5, 0.0],
+    "emissiveTexture": {"index": 0},
+    "extensions": {"KHR_materials_emissive_strength": {"emissiveStrength": 4.0}}
+  }]
+}"#;
+        let doc = gltf::Gltf::from_slice(json.as_bytes())?.document;
+        let images = [gltf::image::Data {
+            pixels: vec![255, 255, 255],
+            format: Format::R8G8B8,
+            width: 1,
+            height: 1,
+        }];
+        let mut importer = Importer::new(&[], &images);
+        let m = importer.material(&doc.materials().next().unwrap())?;
+        let emitted = m.emmitted(0.5, 0.5, &Point3::default());
+        assert_eq!(emitted, Color::new(4.0, 2.0, 0.0));
+        Ok(())
+    }
+}
@@ -1,11 +1,15 @@
+pub mod channel;
 pub mod checker;
 pub mod image_texture;
 pub mod noise;
+pub mod product;
 pub mod solid_color;
 
+pub use channel::Channel;
 pub use checker::Checker;
 pub use image_texture::ImageTexture;
 pub use noise::{Marble, NoiseTexture, Turbulence};
+pub use product::Product;
 pub use solid_color::SolidColor;
 
 use std::sync::Arc;
@@ -0,0 +1,25 @@
+use super::{Texture, TexturePtr};
+use crate::{Color, Point3};
+
+/// Grey texture from one channel of another, for maps that pack several
+/// scalar parameters into the channels of one image.
+#[derive(Clone)]
+pub struct Channel {
+    texture: TexturePtr,
+    /// 0, 1 or 2 for red, green or blue.
+    channel: usize,
+}
+
+impl Channel {
+    pub fn new(texture: TexturePtr, channel: usize) -> Self {
+        assert!(channel < 3, "no color channel {}", channel);
+        Self { texture, channel }
+    }
+}
+
+impl Texture for Channel {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        let c = self.texture.value(u, v, p).0[self.channel];
+        Color::new(c, c, c)
+    }
+}
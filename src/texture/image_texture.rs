@@ -1,8 +1,7 @@
 use std::path::Path;
 
-use anyhow::Context;
 use image::io::Reader as ImageReader;
-use image::{DynamicImage, Rgb, RgbImage};
+use image::{Rgb, RgbImage};
 
 use super::Texture;
 use crate::{Color, Point3};
@@ -13,13 +12,14 @@ pub struct ImageTexture {
 }
 
 impl ImageTexture {
+    /// Loads an image file of any format `image` can decode, dropping alpha.
     pub fn new(path: impl AsRef<Path>) -> anyhow::Result<Self> {
         let img = ImageReader::open(path)?.decode()?;
-        if let DynamicImage::ImageRgb8(img) = img {
-            Ok(Self { img })
-        } else {
-            None.context("Not an RgbImage")
-        }
+        Ok(Self::from_image(img.into_rgb8()))
+    }
+
+    pub fn from_image(img: RgbImage) -> Self {
+        Self { img }
     }
 }
 
@@ -28,8 +28,8 @@ impl Texture for ImageTexture {
         let u = u.clamp(0.0, 1.0);
         let v = 1.0 - v.clamp(0.0, 1.0);
 
-        let i = ((u * self.img.width() as f64) as u32).min(self.img.width());
-        let j = ((v * self.img.height() as f64) as u32).min(self.img.height());
+        let i = ((u * self.img.width() as f64) as u32).min(self.img.width() - 1);
+        let j = ((v * self.img.height() as f64) as u32).min(self.img.height() - 1);
 
         let color_scale = 1.0 / 255.0;
         let &Rgb([r, g, b]) = self.img.get_pixel(i, j);
@@ -48,7 +48,7 @@ mod tests {
     #[test]
     fn test_decode_jpeg() -> anyhow::Result<()> {
         let img = ImageReader::open("res/earthmap.jpg")?.decode()?;
-        assert!(matches!(img, DynamicImage::ImageRgb8(_)));
+        assert!(matches!(img, image::DynamicImage::ImageRgb8(_)));
         Ok(())
     }
 }
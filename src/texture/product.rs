@@ -0,0 +1,21 @@
+use super::{Texture, TexturePtr};
+use crate::{Color, Point3};
+
+/// Channel-wise product of two textures, e.g. a map scaled by a factor.
+#[derive(Clone)]
+pub struct Product {
+    a: TexturePtr,
+    b: TexturePtr,
+}
+
+impl Product {
+    pub fn new(a: TexturePtr, b: TexturePtr) -> Self {
+        Self { a, b }
+    }
+}
+
+impl Texture for Product {
+    fn value(&self, u: f64, v: f64, p: &Point3) -> Color {
+        self.a.value(u, v, p) * self.b.value(u, v, p)
+    }
+}
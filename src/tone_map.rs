@@ -0,0 +1,121 @@
+use crate::Color;
+
+pub trait ToneMap {
+    /// Compresses linear radiance into the displayable range [0, 1].
+    fn map(&self, c: &Color) -> Color;
+}
+
+pub type ToneMapPtr = Box<dyn ToneMap + Send + Sync>;
+
+/// Encoding applied after tone mapping.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transfer {
+    /// Plain gamma 2 (`sqrt`).
+    Gamma2,
+    /// The piecewise sRGB curve.
+    Srgb,
+}
+
+impl Transfer {
+    pub fn encode(&self, x: f64) -> f64 {
+        match self {
+            Transfer::Gamma2 => x.sqrt(),
+            Transfer::Srgb => {
+                if x <= 0.0031308 {
+                    12.92 * x
+                } else {
+                    1.055 * x.powf(1.0 / 2.4) - 0.055
+                }
+            }
+        }
+    }
+}
+
+/// Converts rendered radiance to 8-bit display values:
+/// exposure, then tone mapping, then the transfer curve.
+pub struct ToneMapping {
+    scale: f64,
+    tone_map: ToneMapPtr,
+    transfer: Transfer,
+}
+
+impl ToneMapping {
+    /// `exposure` is in stops.
+    pub fn new(exposure: f64, tone_map: ToneMapPtr, transfer: Transfer) -> Self {
+        Self {
+            scale: exposure.exp2(),
+            tone_map,
+            transfer,
+        }
+    }
+
+    pub fn apply(&self, c: &Color) -> Color {
+        self.tone_map
+            .map(&(c * self.scale))
+            .map(|x| self.transfer.encode(x.clamp(0.0, 1.0)))
+    }
+
+    pub fn rgb(&self, c: &Color) -> [u8; 3] {
+        const RGB_SCALE: f64 = 256.0;
+        let Color(v) = self.apply(c);
+        let quantize = |x: f64| (RGB_SCALE * x).min(255.0) as u8;
+        [quantize(v.x), quantize(v.y), quantize(v.z)]
+    }
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        Self::new(0.0, Box::new(Linear), Transfer::Gamma2)
+    }
+}
+
+pub mod aces;
+pub mod filmic;
+pub mod linear;
+pub mod reinhard;
+
+pub use aces::Aces;
+pub use filmic::Filmic;
+pub use linear::Linear;
+pub use reinhard::Reinhard;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srgb_curve() {
+        let srgb = Transfer::Srgb;
+        assert_eq!(srgb.encode(0.0), 0.0);
+        assert!((srgb.encode(1.0) - 1.0).abs() < 1e-9);
+        assert!((srgb.encode(0.18) - 0.4614).abs() < 1e-4);
+        // Both pieces meet at the threshold.
+        assert!((12.92 * 0.0031308 - (1.055 * 0.0031308f64.powf(1.0 / 2.4) - 0.055)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn default_matches_gamma2_clamp() {
+        let mapping = ToneMapping::default();
+        assert_eq!(mapping.rgb(&Color::new(0.25, 1.0, 15.0)), [128, 255, 255]);
+        assert_eq!(mapping.rgb(&Color::new(-1.0, 0.0, 0.01)), [0, 0, 25]);
+    }
+
+    #[test]
+    fn operators_compress_highlights() {
+        let operators: Vec<ToneMapPtr> = vec![
+            Box::new(Reinhard::default()),
+            Box::new(Aces),
+            Box::new(Filmic::default()),
+        ];
+        for op in operators {
+            let mut prev = -1.0;
+            for &x in &[0.0, 0.1, 0.5, 1.0, 4.0, 15.0, 100.0] {
+                let Color(v) = op.map(&Color::new(x, x, x));
+                assert!(v.x >= prev && v.x <= 1.0);
+                prev = v.x;
+            }
+            // Bright emitters no longer clip to the same value as white.
+            assert!(op.map(&Color::new(1.0, 1.0, 1.0)).0.x < 1.0);
+        }
+    }
+}
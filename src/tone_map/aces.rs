@@ -0,0 +1,15 @@
+use super::ToneMap;
+use crate::Color;
+
+/// Narkowicz's fit of the ACES filmic curve.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Aces;
+
+impl ToneMap for Aces {
+    fn map(&self, c: &Color) -> Color {
+        c.map(|x| {
+            let x = x.max(0.0);
+            (x * (2.51 * x + 0.03) / (x * (2.43 * x + 0.59) + 0.14)).clamp(0.0, 1.0)
+        })
+    }
+}
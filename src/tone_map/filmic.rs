@@ -0,0 +1,36 @@
+use super::ToneMap;
+use crate::Color;
+
+/// Hable's filmic curve from Uncharted 2.
+#[derive(Debug, Clone, Copy)]
+pub struct Filmic {
+    exposure_bias: f64,
+    white: f64,
+}
+
+impl Filmic {
+    pub fn new(exposure_bias: f64, white: f64) -> Self {
+        Self {
+            exposure_bias,
+            white,
+        }
+    }
+
+    fn curve(x: f64) -> f64 {
+        let (a, b, c, d, e, f) = (0.15, 0.50, 0.10, 0.20, 0.02, 0.30);
+        (x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f) - e / f
+    }
+}
+
+impl Default for Filmic {
+    fn default() -> Self {
+        Self::new(2.0, 11.2)
+    }
+}
+
+impl ToneMap for Filmic {
+    fn map(&self, c: &Color) -> Color {
+        let white_scale = 1.0 / Self::curve(self.white);
+        c.map(|x| (Self::curve(self.exposure_bias * x.max(0.0)) * white_scale).clamp(0.0, 1.0))
+    }
+}
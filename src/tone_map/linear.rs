@@ -0,0 +1,12 @@
+use super::ToneMap;
+use crate::Color;
+
+/// No compression; everything above 1 clips.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Linear;
+
+impl ToneMap for Linear {
+    fn map(&self, c: &Color) -> Color {
+        c.clone()
+    }
+}
@@ -0,0 +1,68 @@
+use super::ToneMap;
+use crate::Color;
+
+/// Extended Reinhard on luminance, which keeps hues intact. Colors too
+/// saturated to have that luminance with every channel at most 1 are
+/// desaturated towards the grey that has it.
+#[derive(Debug, Clone, Copy)]
+pub struct Reinhard {
+    white: f64,
+}
+
+impl Reinhard {
+    /// `white` is the smallest luminance mapped to 1.
+    pub fn new(white: f64) -> Self {
+        Self { white }
+    }
+}
+
+impl Default for Reinhard {
+    fn default() -> Self {
+        Self::new(f64::INFINITY)
+    }
+}
+
+impl ToneMap for Reinhard {
+    fn map(&self, c: &Color) -> Color {
+        let l = c.luminance();
+        if l <= 0.0 {
+            return Color::default();
+        }
+        let ld = (l * (1.0 + l / self.white.powi(2)) / (1.0 + l)).min(1.0);
+        let scaled = c * (ld / l);
+        let max = scaled.0.x.max(scaled.0.y).max(scaled.0.z);
+        if max <= 1.0 {
+            return scaled;
+        }
+        // Mixing in grey of the same luminance until the largest channel
+        // comes down to 1.
+        let t = (max - 1.0) / (max - ld);
+        scaled.map(|x| x + t * (ld - x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saturated_colors_stay_in_range() {
+        let reinhard = Reinhard::default();
+        for c in [Color::new(0.0, 0.0, 10.0), Color::new(50.0, 2.0, 0.0)] {
+            let mapped = reinhard.map(&c);
+            let Color(v) = &mapped;
+            assert!([v.x, v.y, v.z]
+                .iter()
+                .all(|x| (0.0..=1.0 + 1e-12).contains(x)));
+            let l = c.luminance();
+            assert!((mapped.luminance() - l / (1.0 + l)).abs() < 1e-12);
+        }
+        // Blue stays the dominant channel.
+        let Color(v) = reinhard.map(&Color::new(0.0, 0.0, 10.0));
+        assert!(
+            (v.z - 1.0).abs() < 1e-12 && v.x < 0.5 && v.x == v.y,
+            "{:?}",
+            v
+        );
+    }
+}
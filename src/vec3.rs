@@ -71,6 +71,17 @@ impl Vec3 {
             }
         }
     }
+    pub fn random_cosine_direction(rng: &mut Random) -> Self {
+        let r1 = rng.unit_f64();
+        let r2 = rng.unit_f64();
+        let z = (1.0 - r2).sqrt();
+
+        let phi = std::f64::consts::TAU * r1;
+        let x = phi.cos() * r2.sqrt();
+        let y = phi.sin() * r2.sqrt();
+
+        Self::new(x, y, z)
+    }
     pub fn to_matrix(a: &Self, b: &Self, c: &Self) -> Vec<Vec<f64>> {
         vec![
             vec![a.x, b.x, c.x],
@@ -111,12 +122,31 @@ impl TryFrom<Vec<f64>> for Vec3 {
     }
 }
 
+impl From<[f64; 3]> for Vec3 {
+    fn from(v: [f64; 3]) -> Self {
+        Self::new(v[0], v[1], v[2])
+    }
+}
+
 impl From<[f32; 3]> for Vec3 {
     fn from(v: [f32; 3]) -> Self {
         Self::new(v[0] as f64, v[1] as f64, v[2] as f64)
     }
 }
 
+impl ops::Index<usize> for Vec3 {
+    type Output = f64;
+
+    fn index(&self, axis: usize) -> &f64 {
+        match axis {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("axis out of range: {}", axis),
+        }
+    }
+}
+
 impl_op_ex!(-|v: &Vec3| -> Vec3 { Vec3::new(-v.x, -v.y, -v.z) });
 
 impl_op_ex!(+|a: &Vec3, b: &Vec3| -> Vec3 { Vec3::new(a.x + b.x, a.y + b.y, a.z + b.z) });